@@ -0,0 +1,265 @@
+use std::cell::{OnceCell, RefCell};
+
+use gdk::{
+    gio,
+    glib::{self, Object},
+    prelude::*,
+    subclass::prelude::*,
+};
+
+use crate::ical::Property;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::Attachment)]
+    pub struct Attachment {
+        /// The URI of the attached file, or an empty string if it is inline.
+        #[property(get, construct_only)]
+        uri: OnceCell<String>,
+        /// The content of the attached file, if it is inline.
+        #[property(get, construct_only)]
+        data: OnceCell<Option<glib::Bytes>>,
+        /// The media type of the attached file, from its FMTTYPE.
+        #[property(get, set)]
+        format_type: RefCell<String>,
+        #[property(get, set)]
+        filename: RefCell<String>,
+        /// The iCalendar property this attachment was created from, keeping the parameters that
+        /// are not modeled.
+        pub(super) property: RefCell<Option<Property>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Attachment {
+        const NAME: &'static str = "Attachment";
+        type Type = super::Attachment;
+        type ParentType = Object;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for Attachment {}
+}
+
+glib::wrapper! {
+    /// A file attached to an event, either by URI or inline.
+    pub struct Attachment(ObjectSubclass<imp::Attachment>);
+}
+
+impl Attachment {
+    fn new(uri: &str, data: Option<glib::Bytes>, format_type: &str, filename: &str) -> Self {
+        glib::Object::builder()
+            .property("uri", uri)
+            .property("data", data)
+            .property("format-type", format_type)
+            .property("filename", filename)
+            .build()
+    }
+
+    /// Create an attachment referring to a URI.
+    pub fn for_uri(uri: &str, format_type: &str, filename: &str) -> Self {
+        Self::new(uri, None, format_type, filename)
+    }
+
+    /// Create an inline attachment with the content of a file.
+    ///
+    /// Its media type is guessed from the file, and its filename is the name of the file.
+    pub fn from_file(file: &gio::File) -> Result<Self, glib::Error> {
+        let (data, _) = file.load_contents(None::<&gio::Cancellable>)?;
+        let info = file.query_info(
+            &format!(
+                "{},{}",
+                gio::FILE_ATTRIBUTE_STANDARD_CONTENT_TYPE,
+                gio::FILE_ATTRIBUTE_STANDARD_DISPLAY_NAME
+            ),
+            gio::FileQueryInfoFlags::NONE,
+            None::<&gio::Cancellable>,
+        )?;
+        let format_type = info
+            .content_type()
+            .and_then(|content_type| gio::content_type_get_mime_type(&content_type))
+            .unwrap_or_else(|| "application/octet-stream".into());
+
+        Ok(Self::new(
+            "",
+            Some(glib::Bytes::from(&data[..])),
+            &format_type,
+            &info.display_name(),
+        ))
+    }
+
+    /// Create an attachment from an ATTACH property.
+    ///
+    /// Returns `None` if the property holds invalid inline data.
+    pub(crate) fn from_property(property: &Property) -> Option<Self> {
+        let is_inline = property
+            .param("VALUE")
+            .is_some_and(|value| value.eq_ignore_ascii_case("BINARY"))
+            || property
+                .param("ENCODING")
+                .is_some_and(|encoding| encoding.eq_ignore_ascii_case("BASE64"));
+        let format_type = property.param("FMTTYPE").unwrap_or_default();
+        let filename = property
+            .param("FILENAME")
+            .or_else(|| property.param("X-FILENAME"))
+            .unwrap_or_default();
+
+        let attachment = if is_inline {
+            let data = glib::base64_decode(property.value.trim());
+            if data.is_empty() && !property.value.trim().is_empty() {
+                return None;
+            }
+            Self::new(
+                "",
+                Some(glib::Bytes::from_owned(data)),
+                format_type,
+                filename,
+            )
+        } else {
+            Self::for_uri(property.value.trim(), format_type, filename)
+        };
+        attachment.imp().property.replace(Some(property.clone()));
+        Some(attachment)
+    }
+
+    /// The ATTACH property describing this attachment.
+    pub(crate) fn to_property(&self) -> Property {
+        let mut property = self
+            .imp()
+            .property
+            .borrow()
+            .clone()
+            .unwrap_or_else(|| Property::new("ATTACH", ""));
+        match self.data() {
+            Some(data) => {
+                property.value = glib::base64_encode(&data).to_string();
+                property.set_param("ENCODING", "BASE64");
+                property.set_param("VALUE", "BINARY");
+            }
+            None => {
+                property.value = self.uri();
+                property.remove_param("ENCODING");
+                property.remove_param("VALUE");
+            }
+        }
+
+        match self.format_type() {
+            format_type if format_type.is_empty() => property.remove_param("FMTTYPE"),
+            format_type => property.set_param("FMTTYPE", &format_type),
+        }
+        property.remove_param("X-FILENAME");
+        match self.filename() {
+            filename if filename.is_empty() => property.remove_param("FILENAME"),
+            filename => property.set_param("FILENAME", &filename),
+        }
+        property
+    }
+
+    /// Whether the content of the attached file is stored in the event.
+    pub fn is_inline(&self) -> bool {
+        self.data().is_some()
+    }
+
+    /// Save the attached file to `file`, replacing it if it exists.
+    ///
+    /// Inline content is written directly, while attachments referring to a URI are copied from
+    /// it.
+    pub fn save(&self, file: &gio::File) -> Result<(), glib::Error> {
+        match self.data() {
+            Some(data) => file
+                .replace_contents(
+                    &data,
+                    None,
+                    false,
+                    gio::FileCreateFlags::REPLACE_DESTINATION,
+                    None::<&gio::Cancellable>,
+                )
+                .map(|_| ()),
+            None => gio::File::for_uri(&self.uri()).copy(
+                file,
+                gio::FileCopyFlags::OVERWRITE,
+                None::<&gio::Cancellable>,
+                None,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ical::Component;
+
+    fn property(line: &str) -> Property {
+        Component::parse(&format!("BEGIN:VEVENT\r\n{line}\r\nEND:VEVENT\r\n"))
+            .unwrap()
+            .properties
+            .remove(0)
+    }
+
+    #[test]
+    fn parses_inline_data() {
+        let attachment = Attachment::from_property(&property(
+            "ATTACH;FMTTYPE=text/plain;ENCODING=BASE64;VALUE=BINARY;X-FILENAME=hello.txt:\
+             SGVsbG8sIHdvcmxkIQ==",
+        ))
+        .unwrap();
+        assert!(attachment.is_inline());
+        assert_eq!(attachment.uri(), "");
+        assert_eq!(&attachment.data().unwrap()[..], b"Hello, world!");
+        assert_eq!(attachment.format_type(), "text/plain");
+        assert_eq!(attachment.filename(), "hello.txt");
+    }
+
+    #[test]
+    fn parses_uri() {
+        let attachment = Attachment::from_property(&property(
+            "ATTACH;FMTTYPE=application/pdf:https://example.com/agenda.pdf",
+        ))
+        .unwrap();
+        assert!(!attachment.is_inline());
+        assert_eq!(attachment.uri(), "https://example.com/agenda.pdf");
+        assert_eq!(attachment.format_type(), "application/pdf");
+        assert_eq!(attachment.filename(), "");
+    }
+
+    #[test]
+    fn rejects_invalid_inline_data() {
+        assert!(Attachment::from_property(&property("ATTACH;ENCODING=BASE64:%%%")).is_none());
+    }
+
+    #[test]
+    fn round_trips() {
+        // Modeled parameters are written after the others
+        for line in [
+            "ATTACH;X-LABEL=Slides;ENCODING=BASE64;VALUE=BINARY;FMTTYPE=text/plain;\
+             FILENAME=hello.txt:SGVsbG8sIHdvcmxkIQ==",
+            "ATTACH;X-LABEL=Agenda;FMTTYPE=application/pdf:https://example.com/agenda.pdf",
+        ] {
+            let original = property(line);
+            let attachment = Attachment::from_property(&original).unwrap();
+            assert_eq!(attachment.to_property(), original);
+        }
+    }
+
+    #[test]
+    fn writes_changes() {
+        let attachment = Attachment::from_property(&property(
+            "ATTACH;FMTTYPE=text/plain;X-FILENAME=notes.txt:https://example.com/notes",
+        ))
+        .unwrap();
+        attachment.set_format_type("text/markdown");
+        attachment.set_filename("notes.md");
+        let written = attachment.to_property();
+        assert_eq!(written.param("FMTTYPE"), Some("text/markdown"));
+        assert_eq!(written.param("FILENAME"), Some("notes.md"));
+        assert_eq!(written.param("X-FILENAME"), None);
+
+        attachment.set_format_type("");
+        assert_eq!(attachment.to_property().param("FMTTYPE"), None);
+
+        let new = Attachment::for_uri("file:///tmp/report.odt", "", "").to_property();
+        assert_eq!(new, property("ATTACH:file:///tmp/report.odt"));
+    }
+}
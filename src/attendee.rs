@@ -0,0 +1,307 @@
+use std::cell::{Cell, RefCell};
+
+use gdk::{
+    glib::{self, Object},
+    prelude::*,
+    subclass::prelude::*,
+};
+
+use crate::ical::Property;
+
+/// The role of an attendee in a meeting, from its iCalendar ROLE.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "AttendeeRole")]
+pub enum AttendeeRole {
+    Chair,
+    #[default]
+    RequiredParticipant,
+    OptionalParticipant,
+    NonParticipant,
+}
+
+impl AttendeeRole {
+    fn from_ical(value: &str) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "CHAIR" => Self::Chair,
+            "OPT-PARTICIPANT" => Self::OptionalParticipant,
+            "NON-PARTICIPANT" => Self::NonParticipant,
+            _ => Self::RequiredParticipant,
+        }
+    }
+
+    fn as_ical(self) -> &'static str {
+        match self {
+            Self::Chair => "CHAIR",
+            Self::RequiredParticipant => "REQ-PARTICIPANT",
+            Self::OptionalParticipant => "OPT-PARTICIPANT",
+            Self::NonParticipant => "NON-PARTICIPANT",
+        }
+    }
+}
+
+/// The response of an attendee to an invitation, from its iCalendar PARTSTAT.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "ParticipationStatus")]
+pub enum ParticipationStatus {
+    #[default]
+    NeedsAction,
+    Accepted,
+    Declined,
+    Tentative,
+    Delegated,
+}
+
+impl ParticipationStatus {
+    fn from_ical(value: &str) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "ACCEPTED" => Self::Accepted,
+            "DECLINED" => Self::Declined,
+            "TENTATIVE" => Self::Tentative,
+            "DELEGATED" => Self::Delegated,
+            _ => Self::NeedsAction,
+        }
+    }
+
+    pub(crate) fn as_ical(self) -> &'static str {
+        match self {
+            Self::NeedsAction => "NEEDS-ACTION",
+            Self::Accepted => "ACCEPTED",
+            Self::Declined => "DECLINED",
+            Self::Tentative => "TENTATIVE",
+            Self::Delegated => "DELEGATED",
+        }
+    }
+}
+
+/// The kind of calendar user an attendee is, from its iCalendar CUTYPE.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "CalendarUserType")]
+pub enum CalendarUserType {
+    #[default]
+    Individual,
+    Group,
+    Resource,
+    Room,
+    Unknown,
+}
+
+impl CalendarUserType {
+    fn from_ical(value: &str) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "INDIVIDUAL" => Self::Individual,
+            "GROUP" => Self::Group,
+            "RESOURCE" => Self::Resource,
+            "ROOM" => Self::Room,
+            _ => Self::Unknown,
+        }
+    }
+
+    fn as_ical(self) -> &'static str {
+        match self {
+            Self::Individual => "INDIVIDUAL",
+            Self::Group => "GROUP",
+            Self::Resource => "RESOURCE",
+            Self::Room => "ROOM",
+            Self::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::Attendee)]
+    pub struct Attendee {
+        /// The email address, without the `mailto:` scheme.
+        #[property(get, set)]
+        email: RefCell<String>,
+        #[property(get, set)]
+        common_name: RefCell<String>,
+        #[property(get, set, builder(AttendeeRole::default()))]
+        role: Cell<AttendeeRole>,
+        #[property(get, set, builder(ParticipationStatus::default()))]
+        participation_status: Cell<ParticipationStatus>,
+        /// Whether a reply is expected from this attendee.
+        #[property(get, set)]
+        rsvp: Cell<bool>,
+        #[property(get, set, builder(CalendarUserType::default()))]
+        user_type: Cell<CalendarUserType>,
+        /// The iCalendar property this attendee was created from, keeping the parameters that are
+        /// not modeled.
+        pub(super) property: RefCell<Option<Property>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Attendee {
+        const NAME: &'static str = "Attendee";
+        type Type = super::Attendee;
+        type ParentType = Object;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for Attendee {}
+}
+
+glib::wrapper! {
+    /// An attendee or the organizer of an event.
+    pub struct Attendee(ObjectSubclass<imp::Attendee>);
+}
+
+impl Attendee {
+    /// Create an attendee from its email address and name.
+    pub fn new(email: &str, common_name: &str) -> Self {
+        glib::Object::builder()
+            .property("email", email)
+            .property("common-name", common_name)
+            .build()
+    }
+
+    /// Create an attendee from an ATTENDEE or ORGANIZER property.
+    pub(crate) fn from_property(property: &Property) -> Self {
+        let param = |name| property.param(name).unwrap_or_default();
+        let attendee: Self = glib::Object::builder()
+            .property("email", email_from_address(&property.value))
+            .property("common-name", param("CN"))
+            .property("role", AttendeeRole::from_ical(param("ROLE")))
+            .property(
+                "participation-status",
+                ParticipationStatus::from_ical(param("PARTSTAT")),
+            )
+            .property("rsvp", param("RSVP").eq_ignore_ascii_case("TRUE"))
+            .property(
+                "user-type",
+                property
+                    .param("CUTYPE")
+                    .map_or_else(CalendarUserType::default, CalendarUserType::from_ical),
+            )
+            .build();
+        attendee.imp().property.replace(Some(property.clone()));
+        attendee
+    }
+
+    /// The property with the given name describing this attendee, either ATTENDEE or ORGANIZER.
+    pub(crate) fn to_property(&self, name: &str) -> Property {
+        let mut property = self
+            .imp()
+            .property
+            .borrow()
+            .clone()
+            .unwrap_or_else(|| Property::new(name, ""));
+        property.name = name.to_string();
+        property.value = format!("mailto:{}", self.email());
+
+        match self.common_name() {
+            common_name if common_name.is_empty() => property.remove_param("CN"),
+            common_name => property.set_param("CN", &common_name),
+        }
+        if name == "ATTENDEE" {
+            property.set_param("ROLE", self.role().as_ical());
+            property.set_param("PARTSTAT", self.participation_status().as_ical());
+            if self.rsvp() {
+                property.set_param("RSVP", "TRUE");
+            } else {
+                property.remove_param("RSVP");
+            }
+            property.set_param("CUTYPE", self.user_type().as_ical());
+        }
+        property
+    }
+}
+
+/// The email address of a CAL-ADDRESS value, e.g. `mailto:jane@example.com`.
+pub(crate) fn email_from_address(address: &str) -> String {
+    match address.get(..7) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("mailto:") => address[7..].to_string(),
+        _ => address.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ical::Component;
+
+    fn property(line: &str) -> Property {
+        Component::parse(&format!("BEGIN:VEVENT\r\n{line}\r\nEND:VEVENT\r\n"))
+            .unwrap()
+            .properties
+            .remove(0)
+    }
+
+    #[test]
+    fn parses_parameters() {
+        let attendee = Attendee::from_property(&property(
+            "ATTENDEE;CN=\"Doe, Jane\";ROLE=OPT-PARTICIPANT;PARTSTAT=TENTATIVE;RSVP=TRUE;\
+             CUTYPE=ROOM:MAILTO:jane@example.com",
+        ));
+        assert_eq!(attendee.email(), "jane@example.com");
+        assert_eq!(attendee.common_name(), "Doe, Jane");
+        assert_eq!(attendee.role(), AttendeeRole::OptionalParticipant);
+        assert_eq!(
+            attendee.participation_status(),
+            ParticipationStatus::Tentative
+        );
+        assert!(attendee.rsvp());
+        assert_eq!(attendee.user_type(), CalendarUserType::Room);
+    }
+
+    #[test]
+    fn defaults_missing_parameters() {
+        let attendee = Attendee::from_property(&property("ATTENDEE:mailto:bob@example.com"));
+        assert_eq!(attendee.email(), "bob@example.com");
+        assert_eq!(attendee.common_name(), "");
+        assert_eq!(attendee.role(), AttendeeRole::RequiredParticipant);
+        assert_eq!(
+            attendee.participation_status(),
+            ParticipationStatus::NeedsAction
+        );
+        assert!(!attendee.rsvp());
+        assert_eq!(attendee.user_type(), CalendarUserType::Individual);
+    }
+
+    #[test]
+    fn round_trips() {
+        // Modeled parameters are written after the others
+        let original = property(
+            "ATTENDEE;DELEGATED-FROM=\"mailto:boss@example.com\";CN=\"Doe, Jane\";\
+             ROLE=CHAIR;PARTSTAT=ACCEPTED;RSVP=TRUE;CUTYPE=INDIVIDUAL:mailto:jane@example.com",
+        );
+        let attendee = Attendee::from_property(&original);
+        assert_eq!(attendee.to_property("ATTENDEE"), original);
+    }
+
+    #[test]
+    fn writes_changes() {
+        let attendee = Attendee::from_property(&property(
+            "ATTENDEE;CN=Jane;PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:jane@example.com",
+        ));
+        attendee.set_participation_status(ParticipationStatus::Declined);
+        attendee.set_rsvp(false);
+        attendee.set_common_name("");
+
+        let property = attendee.to_property("ATTENDEE");
+        assert_eq!(property.value, "mailto:jane@example.com");
+        assert_eq!(property.param("PARTSTAT"), Some("DECLINED"));
+        assert_eq!(property.param("ROLE"), Some("REQ-PARTICIPANT"));
+        assert_eq!(property.param("RSVP"), None);
+        assert_eq!(property.param("CN"), None);
+    }
+
+    #[test]
+    fn writes_organizer() {
+        let organizer = Attendee::new("boss@example.com", "Boss").to_property("ORGANIZER");
+        assert_eq!(
+            organizer,
+            property("ORGANIZER;CN=Boss:mailto:boss@example.com")
+        );
+    }
+
+    #[test]
+    fn strips_mailto_scheme() {
+        assert_eq!(email_from_address("mailto:a@example.com"), "a@example.com");
+        assert_eq!(email_from_address("MAILTO:a@example.com"), "a@example.com");
+        assert_eq!(email_from_address("a@example.com"), "a@example.com");
+        assert_eq!(email_from_address("urn:uuid:1"), "urn:uuid:1");
+    }
+}
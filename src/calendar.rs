@@ -5,14 +5,18 @@ use std::{
 
 use gdk::{
     RGBA,
-    gio::ListStore,
+    gio::{self, ListStore},
     glib::{self, Object, clone, closure_local, subclass::Signal},
     prelude::*,
     subclass::prelude::*,
 };
 use tracing::info;
 
-use crate::{Collection, Event, Manager};
+use crate::{
+    Attendee, Collection, CsvColumn, CsvMapping, Event, ImportError, ImportedComponent, ItipError,
+    Manager, UidConflictPolicy, csv::CsvContent, export_csv, export_ics, ics::IcsContent,
+    itip::ItipMessage,
+};
 
 mod imp {
     use super::*;
@@ -116,10 +120,14 @@ impl Calendar {
 
     /// Signal that this calendar was deleted.
     pub(super) fn emit_deleted(&self) {
-        for event in self.events().iter::<Event>() {
-            event
-                .expect("Model should not be mutated during iteration")
-                .emit_deleted();
+        // Events remove themselves from the model when deleted
+        let events = self
+            .events()
+            .iter::<Event>()
+            .map(|event| event.expect("Model should not be mutated during iteration"))
+            .collect::<Vec<_>>();
+        for event in events {
+            event.emit_deleted();
         }
 
         self.emit_by_name::<()>("deleted", &[]);
@@ -155,4 +163,128 @@ impl Calendar {
         // TODO: dispatch to relevant provider instead
         self.manager().create_event(&self.uri(), name, description);
     }
+
+    /// Import the components of iCalendar data into this calendar.
+    ///
+    /// All the components are created at once, components whose UID is already used in this
+    /// calendar are handled according to `policy`.
+    pub fn import_ics(
+        &self,
+        data: &[u8],
+        policy: UidConflictPolicy,
+    ) -> Result<Vec<ImportedComponent>, ImportError> {
+        let content = IcsContent::parse(data)?;
+        // TODO: dispatch to relevant provider instead
+        self.manager()
+            .import_components(&self.uri(), content, policy)
+    }
+
+    /// Import the components of an iCalendar file into this calendar.
+    ///
+    /// See [`Calendar::import_ics`].
+    pub fn import_ics_file(
+        &self,
+        file: &gio::File,
+        policy: UidConflictPolicy,
+    ) -> Result<Vec<ImportedComponent>, ImportError> {
+        let (data, _) = file
+            .load_contents(None::<&gio::Cancellable>)
+            .map_err(ImportError::Read)?;
+        self.import_ics(&data, policy)
+    }
+
+    /// Import the components of jCal data into this calendar.
+    ///
+    /// See [`Calendar::import_ics`].
+    pub fn import_jcal(
+        &self,
+        data: &str,
+        policy: UidConflictPolicy,
+    ) -> Result<Vec<ImportedComponent>, ImportError> {
+        let content = IcsContent::parse_jcal(data)?;
+        // TODO: dispatch to relevant provider instead
+        self.manager()
+            .import_components(&self.uri(), content, policy)
+    }
+
+    /// Import the components of an xCal document into this calendar.
+    ///
+    /// See [`Calendar::import_ics`].
+    pub fn import_xcal(
+        &self,
+        data: &str,
+        policy: UidConflictPolicy,
+    ) -> Result<Vec<ImportedComponent>, ImportError> {
+        let content = IcsContent::parse_xcal(data)?;
+        // TODO: dispatch to relevant provider instead
+        self.manager()
+            .import_components(&self.uri(), content, policy)
+    }
+
+    /// The email address of the user in this calendar, used to find them among the attendees of
+    /// events.
+    pub fn own_address(&self) -> Option<String> {
+        // TODO: dispatch to relevant provider instead
+        self.manager().own_address(&self.uri())
+    }
+
+    /// The user as an attendee, to organize new meetings in this calendar.
+    pub fn own_attendee(&self) -> Option<Attendee> {
+        let address = self.own_address()?;
+        let name = self.collection().identity_name().unwrap_or_default();
+        Some(Attendee::new(&address, &name))
+    }
+
+    /// Apply an iTIP scheduling message to the events of this calendar.
+    ///
+    /// A REQUEST creates or updates the events it holds, unless the stored events are more
+    /// recent, a CANCEL removes them and a REPLY updates the participation status of the
+    /// attendees that replied. Returns the outcome for each event of the message.
+    pub fn receive_itip(&self, data: &[u8]) -> Result<Vec<ImportedComponent>, ItipError> {
+        let message = ItipMessage::parse(data)?;
+        // TODO: dispatch to relevant provider instead
+        self.manager().receive_itip(&self.uri(), message)
+    }
+
+    /// Import the rows of CSV data as events of this calendar, reading their fields according to
+    /// `mapping`.
+    ///
+    /// Returns the outcome of the import of each row.
+    pub fn import_csv(
+        &self,
+        data: &str,
+        mapping: &CsvMapping,
+    ) -> Result<Vec<ImportedComponent>, ImportError> {
+        let CsvContent { content, rows } = CsvContent::parse(data, mapping);
+        // TODO: dispatch to relevant provider instead
+        let imported =
+            self.manager()
+                .import_components(&self.uri(), content, UidConflictPolicy::Duplicate)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| row.map_or_else(|failed| failed, |index| imported[index].clone()))
+            .collect())
+    }
+
+    /// Export all the events of this calendar as a single iCalendar VCALENDAR, written to
+    /// `stream`.
+    pub fn export_ics(&self, stream: &impl IsA<gio::OutputStream>) -> Result<(), glib::Error> {
+        let events = self
+            .events()
+            .iter::<Event>()
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Model should not be mutated during iteration");
+        export_ics(&events, stream)
+    }
+
+    /// Export all the events of this calendar as CSV, with the given columns.
+    pub fn export_csv(&self, columns: &[CsvColumn]) -> String {
+        let events = self
+            .events()
+            .iter::<Event>()
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Model should not be mutated during iteration");
+        export_csv(&events, columns)
+    }
 }
@@ -8,7 +8,7 @@ use gdk::{
     subclass::prelude::*,
 };
 
-use crate::{Calendar, Manager, Provider};
+use crate::{Calendar, Manager, MemoList, Provider, TaskList};
 
 mod imp {
     use super::*;
@@ -24,8 +24,18 @@ mod imp {
         uri: OnceCell<String>,
         #[property(get, set)]
         name: RefCell<String>,
+        /// The email address of the user of this collection, if known.
+        #[property(get, set)]
+        identity_address: RefCell<Option<String>>,
+        /// The name of the user of this collection, if known.
+        #[property(get, set)]
+        identity_name: RefCell<Option<String>>,
         #[property(get)]
         calendars: OnceCell<ListStore>,
+        #[property(get)]
+        task_lists: OnceCell<ListStore>,
+        #[property(get)]
+        memo_lists: OnceCell<ListStore>,
     }
 
     #[glib::object_subclass]
@@ -42,6 +52,8 @@ mod imp {
             self.parent_constructed();
 
             self.calendars.get_or_init(ListStore::new::<Calendar>);
+            self.task_lists.get_or_init(ListStore::new::<TaskList>);
+            self.memo_lists.get_or_init(ListStore::new::<MemoList>);
         }
     }
 
@@ -63,6 +75,18 @@ mod imp {
                 .get()
                 .expect("calendars should be initialized")
         }
+
+        pub fn task_lists(&self) -> &ListStore {
+            self.task_lists
+                .get()
+                .expect("task lists should be initialized")
+        }
+
+        pub fn memo_lists(&self) -> &ListStore {
+            self.memo_lists
+                .get()
+                .expect("memo lists should be initialized")
+        }
     }
 }
 
@@ -99,6 +123,40 @@ impl Collection {
         ));
     }
 
+    /// Add a task list to this collection.
+    pub(crate) fn add_task_list(&self, task_list: &TaskList) {
+        self.imp().task_lists().append(task_list);
+
+        task_list.connect_deleted(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |task_list| {
+                let index = obj
+                    .task_lists()
+                    .find(task_list)
+                    .expect("Task list should be found");
+                obj.task_lists().remove(index);
+            }
+        ));
+    }
+
+    /// Add a memo list to this collection.
+    pub(crate) fn add_memo_list(&self, memo_list: &MemoList) {
+        self.imp().memo_lists().append(memo_list);
+
+        memo_list.connect_deleted(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |memo_list| {
+                let index = obj
+                    .memo_lists()
+                    .find(memo_list)
+                    .expect("Memo list should be found");
+                obj.memo_lists().remove(index);
+            }
+        ));
+    }
+
     /// Ask the backend to create a new calendar in this collection.
     pub fn create_calendar(&self, name: &str, color: RGBA) {
         // TODO: dispatch to relevant provider instead
@@ -0,0 +1,572 @@
+use jiff::{
+    Span,
+    civil::{Date, DateTime, Time},
+    tz::TimeZone,
+};
+
+use crate::{
+    Event, ImportStatus, ImportedComponent,
+    ical::{self, Component, Property},
+    ics::IcsContent,
+};
+
+/// An event field stored in a CSV column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvColumn {
+    Summary,
+    Start,
+    End,
+    /// The time of day of the start, when [`CsvColumn::Start`] only holds its date.
+    StartTime,
+    /// The time of day of the end, when [`CsvColumn::End`] only holds its date.
+    EndTime,
+    AllDay,
+    Location,
+    Description,
+    /// The name of the calendar of the event, ignored when importing.
+    CalendarName,
+}
+
+impl CsvColumn {
+    /// The name of this column in exported headers.
+    pub fn header(self) -> &'static str {
+        match self {
+            Self::Summary => "Summary",
+            Self::Start => "Start",
+            Self::End => "End",
+            Self::StartTime => "Start Time",
+            Self::EndTime => "End Time",
+            Self::AllDay => "All Day",
+            Self::Location => "Location",
+            Self::Description => "Description",
+            Self::CalendarName => "Calendar",
+        }
+    }
+}
+
+/// How the columns of a CSV file map to event fields.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CsvMapping {
+    columns: Vec<(CsvColumn, usize)>,
+    has_header: bool,
+}
+
+impl CsvMapping {
+    /// Create an empty mapping.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read `field` from the column at `index`.
+    pub fn column(mut self, field: CsvColumn, index: usize) -> Self {
+        self.columns.retain(|(column, _)| *column != field);
+        self.columns.push((field, index));
+        self
+    }
+
+    /// Whether the first row holds column names and must be skipped.
+    pub fn has_header(mut self, has_header: bool) -> Self {
+        self.has_header = has_header;
+        self
+    }
+
+    fn field<'a>(&self, row: &'a [String], field: CsvColumn) -> Option<&'a str> {
+        let (_, index) = self.columns.iter().find(|(column, _)| *column == field)?;
+        row.get(*index)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// Export events as CSV, with a header row and the given columns.
+///
+/// Timed events are written with their UTC offset, all-day events as dates. If a time column is
+/// included, the matching date column only holds the date, and both are in the system time zone.
+pub fn export_csv(events: &[Event], columns: &[CsvColumn]) -> String {
+    let split_start = columns.contains(&CsvColumn::StartTime);
+    let split_end = columns.contains(&CsvColumn::EndTime);
+    let mut csv = String::new();
+    write_row(
+        &mut csv,
+        columns.iter().map(|column| column.header().to_owned()),
+    );
+
+    for event in events {
+        let timeframe = event.timeframe();
+        let all_day = timeframe
+            .as_ref()
+            .is_some_and(|timeframe| timeframe.all_day());
+        let format = |zoned: &jiff::Zoned, split: bool| {
+            if all_day {
+                zoned.strftime("%Y-%m-%d").to_string()
+            } else if split {
+                zoned
+                    .with_time_zone(TimeZone::system())
+                    .strftime("%Y-%m-%d")
+                    .to_string()
+            } else {
+                zoned.strftime("%Y-%m-%dT%H:%M:%S%:z").to_string()
+            }
+        };
+        let format_time = |zoned: &jiff::Zoned| {
+            if all_day {
+                String::new()
+            } else {
+                zoned
+                    .with_time_zone(TimeZone::system())
+                    .strftime("%H:%M:%S")
+                    .to_string()
+            }
+        };
+
+        write_row(
+            &mut csv,
+            columns.iter().map(|column| match column {
+                CsvColumn::Summary => event.name(),
+                CsvColumn::Start => timeframe
+                    .as_ref()
+                    .map(|timeframe| format(&timeframe.start().0, split_start))
+                    .unwrap_or_default(),
+                CsvColumn::End => timeframe
+                    .as_ref()
+                    .map(|timeframe| format(&timeframe.end().0, split_end))
+                    .unwrap_or_default(),
+                CsvColumn::StartTime => timeframe
+                    .as_ref()
+                    .map(|timeframe| format_time(&timeframe.start().0))
+                    .unwrap_or_default(),
+                CsvColumn::EndTime => timeframe
+                    .as_ref()
+                    .map(|timeframe| format_time(&timeframe.end().0))
+                    .unwrap_or_default(),
+                CsvColumn::AllDay => all_day.to_string(),
+                CsvColumn::Location => event.location(),
+                CsvColumn::Description => event.description(),
+                CsvColumn::CalendarName => event.calendar().name(),
+            }),
+        );
+    }
+
+    csv
+}
+
+/// The rows of a CSV file converted to VEVENT components.
+pub(crate) struct CsvContent {
+    pub content: IcsContent,
+    /// For each row, the index of its component in `content`, or why it could not be converted.
+    pub rows: Vec<Result<usize, ImportedComponent>>,
+}
+
+impl CsvContent {
+    /// Parse CSV data into VEVENT components according to `mapping`.
+    ///
+    /// The date convention is detected from the start and end columns, each value may then be a
+    /// date or a date and time.
+    pub fn parse(data: &str, mapping: &CsvMapping) -> Self {
+        let mut records = parse_records(data);
+        if mapping.has_header && !records.is_empty() {
+            records.remove(0);
+        }
+
+        let dates = records
+            .iter()
+            .flat_map(|row| {
+                [
+                    mapping.field(row, CsvColumn::Start),
+                    mapping.field(row, CsvColumn::End),
+                ]
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+        let convention = DateConvention::detect(&dates);
+
+        let mut components = Vec::new();
+        let rows = records
+            .iter()
+            .map(|row| match row_to_component(row, mapping, convention) {
+                Ok(component) => {
+                    components.push(component);
+                    Ok(components.len() - 1)
+                }
+                Err(reason) => Err(ImportedComponent {
+                    uid: String::new(),
+                    summary: mapping
+                        .field(row, CsvColumn::Summary)
+                        .unwrap_or_default()
+                        .to_owned(),
+                    status: ImportStatus::Failed(reason),
+                }),
+            })
+            .collect();
+
+        Self {
+            content: IcsContent {
+                vtimezones: Vec::new(),
+                components,
+            },
+            rows,
+        }
+    }
+}
+
+fn row_to_component(
+    row: &[String],
+    mapping: &CsvMapping,
+    convention: Option<DateConvention>,
+) -> Result<Component, String> {
+    // Read a date column, along with its time column if the time is stored separately
+    let date_time = |date_column, time_column, name: &str| -> Result<_, String> {
+        let Some(date) = mapping.field(row, date_column) else {
+            return Ok(None);
+        };
+        let convention = convention.ok_or_else(|| format!("unknown date format: {date}"))?;
+        let parsed = match mapping.field(row, time_column) {
+            Some(time) => convention
+                .parse_split(date, time)
+                .map(|zoned| (zoned, false)),
+            None => convention.parse(date),
+        };
+        parsed
+            .map(Some)
+            .ok_or_else(|| format!("invalid {name}: {date}"))
+    };
+
+    let (start, date_only) = date_time(CsvColumn::Start, CsvColumn::StartTime, "start")?
+        .ok_or_else(|| "missing start".to_string())?;
+    let all_day = match mapping.field(row, CsvColumn::AllDay) {
+        Some(all_day) => {
+            parse_bool(all_day).ok_or_else(|| format!("invalid all day: {all_day}"))?
+        }
+        None => date_only,
+    };
+
+    let end = match date_time(CsvColumn::End, CsvColumn::EndTime, "end")? {
+        Some((end, _)) => end,
+        None if all_day => start
+            .checked_add(Span::new().days(1))
+            .map_err(|err| err.to_string())?,
+        None => start.clone(),
+    };
+
+    let mut component = Component::new("VEVENT");
+    component.set_text("UID", &gdk::glib::uuid_string_random());
+    component.set_property(ical::date_time_property(
+        "DTSTAMP",
+        &jiff::Zoned::now().with_time_zone(TimeZone::UTC),
+        false,
+    ));
+    component.set_property(ical::date_time_property("DTSTART", &start, all_day));
+    component.set_property(ical::date_time_property("DTEND", &end, all_day));
+    for (field, name) in [
+        (CsvColumn::Summary, "SUMMARY"),
+        (CsvColumn::Location, "LOCATION"),
+        (CsvColumn::Description, "DESCRIPTION"),
+    ] {
+        if let Some(value) = mapping.field(row, field) {
+            component.set_property(Property::text(name, value));
+        }
+    }
+    Ok(component)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "y" | "x" | "1" => Some(true),
+        "false" | "no" | "n" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// A format of the dates in a CSV file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateFormat {
+    /// A date and a time with a UTC offset.
+    Offset(&'static str),
+    /// A date and a time in the system time zone.
+    DateTime(&'static str),
+    /// A date, for all-day events.
+    Date(&'static str),
+}
+
+impl DateFormat {
+    /// Parse a value, returning the time and whether it was a date.
+    fn parse(self, value: &str) -> Option<(jiff::Zoned, bool)> {
+        match self {
+            Self::Offset(format) if format.ends_with('Z') => DateTime::strptime(format, value)
+                .ok()?
+                .to_zoned(TimeZone::UTC)
+                .ok()
+                .map(|zoned| (zoned, false)),
+            Self::Offset(format) => jiff::Zoned::strptime(format, value)
+                .ok()
+                .map(|zoned| (zoned, false)),
+            Self::DateTime(format) => DateTime::strptime(format, value)
+                .ok()?
+                .to_zoned(TimeZone::system())
+                .ok()
+                .map(|zoned| (zoned, false)),
+            // Dates are floating, at midnight in the system time zone like the DATE values of
+            // iCalendar
+            Self::Date(format) => Date::strptime(format, value)
+                .ok()?
+                .to_zoned(TimeZone::system())
+                .ok()
+                .map(|zoned| (zoned, true)),
+        }
+    }
+}
+
+/// The formats of the times of day in separate time columns, in order of preference.
+const TIME_FORMATS: &[&str] = &["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"];
+
+/// A way of writing the dates of a CSV file, with the formats of its dates and of its dates and
+/// times, in order of preference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DateConvention(&'static [DateFormat]);
+
+impl DateConvention {
+    /// The candidate conventions, in order of preference.
+    ///
+    /// Ambiguous month and day orders are resolved by requiring all the values to parse in the
+    /// same convention, while each value may be a date or a date and time.
+    const CANDIDATES: &[Self] = &[
+        Self(&[
+            DateFormat::Offset("%Y-%m-%dT%H:%M:%S%:z"),
+            DateFormat::Offset("%Y-%m-%dT%H:%M:%SZ"),
+            DateFormat::DateTime("%Y-%m-%dT%H:%M:%S"),
+            DateFormat::DateTime("%Y-%m-%d %H:%M:%S"),
+            DateFormat::DateTime("%Y-%m-%d %H:%M"),
+            DateFormat::Date("%Y-%m-%d"),
+        ]),
+        Self(&[
+            DateFormat::DateTime("%m/%d/%Y %H:%M:%S"),
+            DateFormat::DateTime("%m/%d/%Y %H:%M"),
+            DateFormat::DateTime("%m/%d/%Y %I:%M %p"),
+            DateFormat::Date("%m/%d/%Y"),
+        ]),
+        Self(&[
+            DateFormat::DateTime("%d/%m/%Y %H:%M:%S"),
+            DateFormat::DateTime("%d/%m/%Y %H:%M"),
+            DateFormat::Date("%d/%m/%Y"),
+        ]),
+        Self(&[
+            DateFormat::DateTime("%d.%m.%Y %H:%M:%S"),
+            DateFormat::DateTime("%d.%m.%Y %H:%M"),
+            DateFormat::Date("%d.%m.%Y"),
+        ]),
+    ];
+
+    /// Detect the first convention all the values are written in.
+    fn detect(values: &[&str]) -> Option<Self> {
+        Self::CANDIDATES
+            .iter()
+            .copied()
+            .find(|convention| values.iter().all(|value| convention.parse(value).is_some()))
+    }
+
+    /// Parse a value with the first matching format, returning the time and whether it was a
+    /// date.
+    fn parse(self, value: &str) -> Option<(jiff::Zoned, bool)> {
+        self.0.iter().find_map(|format| format.parse(value))
+    }
+
+    /// Parse a date and the time of day of a separate column, in the system time zone.
+    fn parse_split(self, date: &str, time: &str) -> Option<jiff::Zoned> {
+        let date = self.0.iter().find_map(|format| match format {
+            DateFormat::Date(format) => Date::strptime(format, date).ok(),
+            _ => None,
+        })?;
+        let time = TIME_FORMATS
+            .iter()
+            .find_map(|format| Time::strptime(format, time).ok())?;
+        date.to_datetime(time).to_zoned(TimeZone::system()).ok()
+    }
+}
+
+/// Parse RFC 4180 records, with quoted fields and `""` escapes.
+fn parse_records(data: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = data.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    records.retain(|record| record.iter().any(|field| !field.is_empty()));
+    records
+}
+
+fn write_row(csv: &mut String, fields: impl Iterator<Item = String>) {
+    let fields = fields
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect::<Vec<_>>();
+    csv.push_str(&fields.join(","));
+    csv.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|field| field.to_string()).collect()
+    }
+
+    fn date_time(value: &str) -> jiff::Zoned {
+        value
+            .parse::<DateTime>()
+            .unwrap()
+            .to_zoned(TimeZone::system())
+            .unwrap()
+    }
+
+    #[test]
+    fn detects_conventions() {
+        let iso = DateConvention::CANDIDATES[0];
+        let us = DateConvention::CANDIDATES[1];
+        let european = DateConvention::CANDIDATES[2];
+
+        assert_eq!(
+            DateConvention::detect(&["2025-01-02 10:00", "2025-01-03"]),
+            Some(iso)
+        );
+        assert_eq!(
+            DateConvention::detect(&["01/02/2025", "01/03/2025 10:00"]),
+            Some(us)
+        );
+        assert_eq!(
+            DateConvention::detect(&["01/02/2025", "13/02/2025 10:00"]),
+            Some(european)
+        );
+        assert_eq!(DateConvention::detect(&["2025-01-02", "13/02/2025"]), None);
+    }
+
+    #[test]
+    fn parses_mixed_values() {
+        let us = DateConvention::CANDIDATES[1];
+        assert_eq!(
+            us.parse("01/02/2025 2:30 PM"),
+            Some((date_time("2025-01-02T14:30"), false))
+        );
+        assert_eq!(
+            us.parse("01/02/2025"),
+            Some((date_time("2025-01-02T00:00"), true))
+        );
+
+        let iso = DateConvention::CANDIDATES[0];
+        assert_eq!(
+            iso.parse("2025-01-02T10:00:00Z"),
+            Some((
+                jiff::Zoned::new("2025-01-02T10:00:00Z".parse().unwrap(), TimeZone::UTC),
+                false
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_split_date_and_time() {
+        let european = DateConvention::CANDIDATES[2];
+        assert_eq!(
+            european.parse_split("13/02/2025", "09:15"),
+            Some(date_time("2025-02-13T09:15"))
+        );
+        assert_eq!(
+            european.parse_split("13/02/2025", "9:15 pm"),
+            Some(date_time("2025-02-13T21:15"))
+        );
+        assert_eq!(european.parse_split("13/02/2025", "noon"), None);
+    }
+
+    #[test]
+    fn converts_rows() {
+        let mapping = CsvMapping::new()
+            .column(CsvColumn::Summary, 0)
+            .column(CsvColumn::Start, 1)
+            .column(CsvColumn::StartTime, 2)
+            .column(CsvColumn::End, 3)
+            .column(CsvColumn::EndTime, 4);
+        let convention = DateConvention::detect(&["2025-01-02"]);
+
+        let timed = row_to_component(
+            &row(&["Meeting", "2025-01-02", "10:00", "2025-01-02", "11:30"]),
+            &mapping,
+            convention,
+        )
+        .unwrap();
+        assert_eq!(timed.text("SUMMARY").as_deref(), Some("Meeting"));
+        let (end, is_date) = ical::parse_date_time(timed.property("DTEND").unwrap(), &[]).unwrap();
+        assert_eq!(end.timestamp(), date_time("2025-01-02T11:30").timestamp());
+        assert!(!is_date);
+
+        let all_day = row_to_component(
+            &row(&["Holiday", "2025-01-03", "", "", ""]),
+            &mapping,
+            convention,
+        )
+        .unwrap();
+        let start = all_day.property("DTSTART").unwrap();
+        assert_eq!(start.param("VALUE"), Some("DATE"));
+        assert_eq!(start.value, "20250103");
+        assert_eq!(all_day.property("DTEND").unwrap().value, "20250104");
+
+        assert_eq!(
+            row_to_component(&row(&["No date"]), &mapping, convention),
+            Err("missing start".to_string())
+        );
+        assert_eq!(
+            row_to_component(
+                &row(&["Bad", "2025-01-02", "25:00", "", ""]),
+                &mapping,
+                convention
+            ),
+            Err("invalid start: 2025-01-02".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_records() {
+        let records = parse_records("a,\"b, \"\"c\"\"\"\r\n\r\n\"multi\nline\",d");
+        assert_eq!(
+            records,
+            [row(&["a", "b, \"c\""]), row(&["multi\nline", "d"])]
+        );
+    }
+
+    #[test]
+    fn quotes_fields() {
+        let mut csv = String::new();
+        write_row(
+            &mut csv,
+            ["plain", "with, comma", "with \"quote\""]
+                .into_iter()
+                .map(str::to_owned),
+        );
+        assert_eq!(csv, "plain,\"with, comma\",\"with \"\"quote\"\"\"\r\n");
+    }
+}
@@ -1,15 +1,107 @@
 use std::{
-    cell::{OnceCell, RefCell},
+    cell::{Cell, OnceCell, RefCell},
     sync::LazyLock,
 };
 
 use gdk::{
+    gio::{self, ListStore},
     glib::{self, Object, closure_local, subclass::Signal},
     prelude::*,
     subclass::prelude::*,
 };
+use jiff::tz::TimeZone;
+use tracing::warn;
 
-use crate::{Calendar, Manager, Timeframe};
+use crate::{
+    Attachment, Attendee, Calendar, ItipError, Manager, ParticipationStatus, Timeframe, Zoned,
+    ical::{self, Component, Property, integer},
+};
+
+/// The confirmation of an event, from its iCalendar STATUS.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "EventStatus")]
+pub enum EventStatus {
+    #[default]
+    Undefined,
+    Tentative,
+    Confirmed,
+    Cancelled,
+}
+
+impl EventStatus {
+    fn from_ical(value: &str) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "TENTATIVE" => Self::Tentative,
+            "CONFIRMED" => Self::Confirmed,
+            "CANCELLED" => Self::Cancelled,
+            _ => Self::Undefined,
+        }
+    }
+
+    fn as_ical(self) -> Option<&'static str> {
+        match self {
+            Self::Undefined => None,
+            Self::Tentative => Some("TENTATIVE"),
+            Self::Confirmed => Some("CONFIRMED"),
+            Self::Cancelled => Some("CANCELLED"),
+        }
+    }
+}
+
+/// Whether an event takes up time, from its iCalendar TRANSP.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "EventTransparency")]
+pub enum EventTransparency {
+    /// The event makes its attendees busy.
+    #[default]
+    Opaque,
+    /// The event leaves its attendees free.
+    Transparent,
+}
+
+impl EventTransparency {
+    fn from_ical(value: &str) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "TRANSPARENT" => Self::Transparent,
+            _ => Self::Opaque,
+        }
+    }
+
+    fn as_ical(self) -> &'static str {
+        match self {
+            Self::Opaque => "OPAQUE",
+            Self::Transparent => "TRANSPARENT",
+        }
+    }
+}
+
+/// Who can see an event, from its iCalendar CLASS.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "EventClassification")]
+pub enum EventClassification {
+    #[default]
+    Public,
+    Private,
+    Confidential,
+}
+
+impl EventClassification {
+    fn from_ical(value: &str) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "PRIVATE" => Self::Private,
+            "CONFIDENTIAL" => Self::Confidential,
+            _ => Self::Public,
+        }
+    }
+
+    fn as_ical(self) -> &'static str {
+        match self {
+            Self::Public => "PUBLIC",
+            Self::Private => "PRIVATE",
+            Self::Confidential => "CONFIDENTIAL",
+        }
+    }
+}
 
 mod imp {
 
@@ -30,6 +122,38 @@ mod imp {
         description: RefCell<String>,
         #[property(get, set)]
         timeframe: RefCell<Option<Timeframe>>,
+        #[property(get, set)]
+        organizer: RefCell<Option<Attendee>>,
+        #[property(get)]
+        attendees: OnceCell<ListStore>,
+        #[property(get)]
+        attachments: OnceCell<ListStore>,
+        #[property(get, set)]
+        location: RefCell<String>,
+        #[property(get, set)]
+        url: RefCell<String>,
+        #[property(get, set)]
+        categories: RefCell<Vec<String>>,
+        #[property(get, set, builder(EventStatus::default()))]
+        status: Cell<EventStatus>,
+        #[property(get, set, builder(EventTransparency::default()))]
+        transparency: Cell<EventTransparency>,
+        #[property(get, set, builder(EventClassification::default()))]
+        classification: Cell<EventClassification>,
+        /// From 1 for the highest priority to 9 for the lowest, or 0 if undefined.
+        #[property(get, set, maximum = 9)]
+        priority: Cell<u32>,
+        #[property(get, set)]
+        created: RefCell<Option<Zoned>>,
+        #[property(get, set)]
+        last_modified: RefCell<Option<Zoned>>,
+        /// The revision of the event, incremented by its organizer on significant changes.
+        #[property(get, set)]
+        sequence: Cell<u32>,
+        /// The iCalendar component this event was created from, if any.
+        pub(super) component: RefCell<Option<Component>>,
+        /// The VTIMEZONEs the dates of the component were parsed with.
+        pub(super) vtimezones: RefCell<Vec<Component>>,
     }
 
     #[glib::object_subclass]
@@ -41,12 +165,33 @@ mod imp {
 
     #[glib::derived_properties]
     impl ObjectImpl for Event {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.attendees.get_or_init(ListStore::new::<Attendee>);
+            self.attachments.get_or_init(ListStore::new::<Attachment>);
+        }
+
         fn signals() -> &'static [Signal] {
             static SIGNALS: LazyLock<Vec<Signal>> =
                 LazyLock::new(|| vec![Signal::builder("deleted").build()]);
             SIGNALS.as_ref()
         }
     }
+
+    impl Event {
+        pub fn attendees(&self) -> &ListStore {
+            self.attendees
+                .get()
+                .expect("attendees should be initialized")
+        }
+
+        pub fn attachments(&self) -> &ListStore {
+            self.attachments
+                .get()
+                .expect("attachments should be initialized")
+        }
+    }
 }
 
 glib::wrapper! {
@@ -73,6 +218,306 @@ impl Event {
             .build()
     }
 
+    /// Create an event from its iCalendar component.
+    ///
+    /// Returns `None` if the component has no UID or no valid start.
+    pub(crate) fn from_component(
+        manager: &Manager,
+        calendar: &Calendar,
+        component: &Component,
+        vtimezones: &[Component],
+    ) -> Option<Self> {
+        let uid = component.uid()?;
+        let uri = event_uri(&calendar.uri(), &uid, component.recurrence_id());
+        let timeframe = Timeframe::from_component(component, vtimezones)?;
+
+        let event = Self::new(
+            manager,
+            calendar,
+            &uri,
+            &component.text("SUMMARY").unwrap_or_default(),
+            &component.text("DESCRIPTION").unwrap_or_default(),
+            &timeframe,
+        );
+        event.update_details(component);
+        event.update_participants(component);
+        event.imp().component.replace(Some(component.clone()));
+        event.imp().vtimezones.replace(vtimezones.to_vec());
+        Some(event)
+    }
+
+    /// Update this event from its new iCalendar component.
+    pub(crate) fn update_from_component(&self, component: &Component, vtimezones: &[Component]) {
+        self.set_name(component.text("SUMMARY").unwrap_or_default());
+        self.set_description(component.text("DESCRIPTION").unwrap_or_default());
+        if let Some(timeframe) = Timeframe::from_component(component, vtimezones) {
+            self.set_timeframe(Some(&timeframe));
+        }
+        self.update_details(component);
+        self.update_participants(component);
+        self.imp().component.replace(Some(component.clone()));
+        self.imp().vtimezones.replace(vtimezones.to_vec());
+    }
+
+    fn update_details(&self, component: &Component) {
+        let date_time = |name| {
+            component
+                .property(name)
+                .and_then(|property| ical::parse_date_time(property, &[]))
+                .map(|(date_time, _)| Zoned(date_time))
+        };
+
+        self.set_location(component.text("LOCATION").unwrap_or_default());
+        self.set_url(
+            component
+                .property("URL")
+                .map(|property| property.value.clone())
+                .unwrap_or_default(),
+        );
+        self.set_categories(component.text_values("CATEGORIES"));
+        self.set_status(
+            component
+                .text("STATUS")
+                .map(|status| EventStatus::from_ical(&status))
+                .unwrap_or_default(),
+        );
+        self.set_transparency(
+            component
+                .text("TRANSP")
+                .map(|transp| EventTransparency::from_ical(&transp))
+                .unwrap_or_default(),
+        );
+        self.set_classification(
+            component
+                .text("CLASS")
+                .map(|class| EventClassification::from_ical(&class))
+                .unwrap_or_default(),
+        );
+        self.set_priority(integer(component, "PRIORITY").min(9));
+        self.set_created(date_time("CREATED").as_ref());
+        self.set_last_modified(date_time("LAST-MODIFIED").as_ref());
+        self.set_sequence(integer(component, "SEQUENCE"));
+
+        let attachments = component
+            .properties
+            .iter()
+            .filter(|property| property.name == "ATTACH")
+            .filter_map(Attachment::from_property)
+            .collect::<Vec<_>>();
+        let store = self.imp().attachments();
+        store.splice(0, store.n_items(), &attachments);
+    }
+
+    fn update_participants(&self, component: &Component) {
+        let organizer = component.property("ORGANIZER").map(Attendee::from_property);
+        self.set_organizer(organizer.as_ref());
+
+        let attendees = component
+            .properties
+            .iter()
+            .filter(|property| property.name == "ATTENDEE")
+            .map(Attendee::from_property)
+            .collect::<Vec<_>>();
+        let store = self.imp().attendees();
+        store.splice(0, store.n_items(), &attendees);
+    }
+
+    /// The iCalendar component of this event, reflecting its current properties.
+    pub(crate) fn to_component(&self) -> Component {
+        let original = self.imp().component.borrow().clone();
+        let vtimezones = self.imp().vtimezones.borrow();
+        let mut component = original.clone().unwrap_or_else(|| {
+            let mut component = Component::new("VEVENT");
+            component.set_text("UID", &glib::uuid_string_random());
+            component
+        });
+
+        component.set_text("SUMMARY", &self.name());
+        component.set_text("DESCRIPTION", &self.description());
+
+        // Only rewrite the time frame if it changed, to keep the original time zones
+        if let Some(timeframe) = self.timeframe() {
+            let unchanged = original
+                .as_ref()
+                .and_then(|original| Timeframe::from_component(original, &vtimezones))
+                .is_some_and(|original| {
+                    original.all_day() == timeframe.all_day()
+                        && original.start() == timeframe.start()
+                        && original.end() == timeframe.end()
+                });
+            if !unchanged {
+                let all_day = timeframe.all_day();
+                component.remove_properties("DURATION");
+                component.set_property(ical::date_time_property(
+                    "DTSTART",
+                    &timeframe.start().0,
+                    all_day,
+                ));
+                component.set_property(ical::date_time_property(
+                    "DTEND",
+                    &timeframe.end().0,
+                    all_day,
+                ));
+            }
+        }
+
+        component.set_text("LOCATION", &self.location());
+        match self.url() {
+            url if url.is_empty() => component.remove_properties("URL"),
+            url => component.set_property(Property::new("URL", &url)),
+        }
+        component.set_text_values("CATEGORIES", &self.categories());
+        match self.status().as_ical() {
+            Some(status) => component.set_text("STATUS", status),
+            None => component.remove_properties("STATUS"),
+        }
+        // Only write the default transparency and classification if they were already there
+        let transparency = self.transparency();
+        if transparency != EventTransparency::default() || component.property("TRANSP").is_some() {
+            component.set_text("TRANSP", transparency.as_ical());
+        }
+        let classification = self.classification();
+        if classification != EventClassification::default() || component.property("CLASS").is_some()
+        {
+            component.set_text("CLASS", classification.as_ical());
+        }
+        match self.priority() {
+            0 => component.remove_properties("PRIORITY"),
+            priority => component.set_property(Property::new("PRIORITY", &priority.to_string())),
+        }
+        for (name, value) in [
+            ("CREATED", self.created()),
+            ("LAST-MODIFIED", self.last_modified()),
+        ] {
+            match value {
+                Some(value) => component.set_property(ical::date_time_property(
+                    name,
+                    &value.0.with_time_zone(TimeZone::UTC),
+                    false,
+                )),
+                None => component.remove_properties(name),
+            }
+        }
+        match self.sequence() {
+            0 if component.property("SEQUENCE").is_none() => {}
+            sequence => component.set_property(Property::new("SEQUENCE", &sequence.to_string())),
+        }
+
+        // Keep the attachments where they were, or add them at the end
+        let position = component
+            .properties
+            .iter()
+            .position(|property| property.name == "ATTACH")
+            .unwrap_or(component.properties.len());
+        component.remove_properties("ATTACH");
+        let attachments = self
+            .attachments()
+            .iter::<Attachment>()
+            .map(|attachment| {
+                attachment
+                    .expect("Model should not be mutated during iteration")
+                    .to_property()
+            })
+            .collect::<Vec<_>>();
+        let position = position.min(component.properties.len());
+        component.properties.splice(position..position, attachments);
+
+        // Keep the participants where they were, or add them at the end
+        let position = component
+            .properties
+            .iter()
+            .position(|property| property.name == "ORGANIZER" || property.name == "ATTENDEE")
+            .unwrap_or(component.properties.len());
+        component
+            .properties
+            .retain(|property| property.name != "ORGANIZER" && property.name != "ATTENDEE");
+        let participants = self
+            .organizer()
+            .map(|organizer| organizer.to_property("ORGANIZER"))
+            .into_iter()
+            .chain(self.attendees().iter::<Attendee>().map(|attendee| {
+                attendee
+                    .expect("Model should not be mutated during iteration")
+                    .to_property("ATTENDEE")
+            }))
+            .collect::<Vec<_>>();
+        let position = position.min(component.properties.len());
+        component
+            .properties
+            .splice(position..position, participants);
+
+        component
+    }
+
+    /// Add an attendee to this event, to be stored with [`Event::update`].
+    pub fn add_attendee(&self, attendee: &Attendee) {
+        self.imp().attendees().append(attendee);
+    }
+
+    /// Remove an attendee from this event, to be stored with [`Event::update`].
+    pub fn remove_attendee(&self, attendee: &Attendee) {
+        if let Some(index) = self.attendees().find(attendee) {
+            self.imp().attendees().remove(index);
+        }
+    }
+
+    /// Add an attachment to this event, to be stored with [`Event::update`].
+    pub fn add_attachment(&self, attachment: &Attachment) {
+        self.imp().attachments().append(attachment);
+    }
+
+    /// Attach the content of a local file to this event, to be stored with [`Event::update`].
+    ///
+    /// See [`Attachment::from_file`].
+    pub fn add_attachment_from_file(&self, file: &gio::File) -> Result<Attachment, glib::Error> {
+        let attachment = Attachment::from_file(file)?;
+        self.add_attachment(&attachment);
+        Ok(attachment)
+    }
+
+    /// Remove an attachment from this event, to be stored with [`Event::update`].
+    pub fn remove_attachment(&self, attachment: &Attachment) {
+        if let Some(index) = self.attachments().find(attachment) {
+            self.imp().attachments().remove(index);
+        }
+    }
+
+    /// Ask the backend to store the current properties of this event, including its organizer,
+    /// attendees and attachments.
+    pub fn update(&self) {
+        // TODO: dispatch to relevant provider instead
+        if let Err(err) = self.manager().update_event(self) {
+            warn!("Failed to update event {}: {err}", self.uri());
+        }
+    }
+
+    /// Accept the invitation to this event, and send the reply to its organizer.
+    ///
+    /// Returns the recipients the backend could not deliver the reply to, which must be sent the
+    /// reply by other means, like email. Nothing is sent if the response cannot be stored.
+    pub fn accept(&self) -> Result<Vec<String>, ItipError> {
+        self.respond(ParticipationStatus::Accepted)
+    }
+
+    /// Decline the invitation to this event, and send the reply to its organizer.
+    ///
+    /// See [`Event::accept`].
+    pub fn decline(&self) -> Result<Vec<String>, ItipError> {
+        self.respond(ParticipationStatus::Declined)
+    }
+
+    /// Tentatively accept the invitation to this event, and send the reply to its organizer.
+    ///
+    /// See [`Event::accept`].
+    pub fn accept_tentatively(&self) -> Result<Vec<String>, ItipError> {
+        self.respond(ParticipationStatus::Tentative)
+    }
+
+    fn respond(&self, status: ParticipationStatus) -> Result<Vec<String>, ItipError> {
+        // TODO: dispatch to relevant provider instead
+        self.manager().respond_to_event(self, status)
+    }
+
     /// Signal that this event was deleted.
     pub(super) fn emit_deleted(&self) {
         self.emit_by_name::<()>("deleted", &[]);
@@ -89,3 +534,11 @@ impl Event {
         )
     }
 }
+
+/// Build the URI of an event from its calendar and iCalendar identifiers.
+pub(crate) fn event_uri(calendar_uri: &str, uid: &str, recurrence_id: Option<&str>) -> String {
+    match recurrence_id {
+        Some(recurrence_id) => format!("{calendar_uri}/{uid}/{recurrence_id}"),
+        None => format!("{calendar_uri}/{uid}"),
+    }
+}
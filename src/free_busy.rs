@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+
+use crate::{
+    attendee::email_from_address,
+    ical::{self, Component, Property},
+};
+
+/// A busy interval, from its start included to its end excluded.
+pub(crate) type Interval = (jiff::Zoned, jiff::Zoned);
+
+/// The user and busy intervals of a VFREEBUSY component.
+///
+/// Periods marked as FREE are ignored.
+pub(crate) fn vfreebusy_intervals(vfreebusy: &Component) -> (Option<String>, Vec<Interval>) {
+    let user = vfreebusy
+        .property("ATTENDEE")
+        .or_else(|| vfreebusy.property("ORGANIZER"))
+        .map(|property| email_from_address(&property.value));
+
+    let intervals = vfreebusy
+        .properties
+        .iter()
+        .filter(|property| property.name == "FREEBUSY")
+        .filter(|property| {
+            !property
+                .param("FBTYPE")
+                .is_some_and(|fbtype| fbtype.eq_ignore_ascii_case("FREE"))
+        })
+        .flat_map(ical::split_values)
+        .filter_map(|period| {
+            let (start, end) = period.split_once('/')?;
+            let (start, _) = ical::parse_date_time(&Property::new("DTSTART", start), &[])?;
+            let end = if end.trim_start_matches(['+', '-']).starts_with('P') {
+                start.checked_add(ical::parse_duration(end)?).ok()?
+            } else {
+                ical::parse_date_time(&Property::new("DTEND", end), &[])?.0
+            };
+            Some((start, end))
+        })
+        .collect();
+
+    (user, intervals)
+}
+
+/// The intervals during which the given events keep `own_address` busy, within a window.
+///
+/// Transparent and cancelled events, and events `own_address` declined, are ignored. Recurring
+/// events are expanded, with their detached instances replacing the occurrences they override.
+pub(crate) fn event_intervals(
+    components: &[Component],
+    own_address: &str,
+    vtimezones: &[Component],
+    window: &Interval,
+) -> Vec<Interval> {
+    let (window_start, window_end) = window;
+
+    // The occurrences overridden by detached instances, which are handled on their own
+    let mut overridden = HashMap::<String, Vec<jiff::Zoned>>::new();
+    for component in components {
+        if let (Some(uid), Some(recurrence_id)) =
+            (component.uid(), component.property("RECURRENCE-ID"))
+            && let Some((recurrence_id, _)) = ical::parse_date_time(recurrence_id, vtimezones)
+        {
+            overridden.entry(uid).or_default().push(recurrence_id);
+        }
+    }
+
+    let mut intervals = Vec::new();
+    for component in components {
+        if !is_busy(component, own_address) {
+            continue;
+        }
+        let Some(dtstart) = component.property("DTSTART") else {
+            continue;
+        };
+        let Some((start, is_date)) = ical::parse_date_time(dtstart, vtimezones) else {
+            continue;
+        };
+        // Occurrences last as many days as an all-day event, so that they start and end at
+        // midnight across DST changes, or exactly as long as a timed one (RFC 5545 3.8.5.3)
+        let duration = if let Some(dtend) = component.property("DTEND") {
+            ical::parse_date_time(dtend, vtimezones).and_then(|(end, _)| {
+                if is_date {
+                    start.until((jiff::Unit::Day, &end)).ok()
+                } else {
+                    start.timestamp().until(end.timestamp()).ok()
+                }
+            })
+        } else if let Some(duration) = component.property("DURATION") {
+            ical::parse_duration(&duration.value)
+        } else if is_date {
+            Some(jiff::Span::new().days(1))
+        } else {
+            Some(jiff::Span::new())
+        };
+        let Some(duration) = duration else {
+            continue;
+        };
+
+        let starts = match component.property("RRULE") {
+            Some(rrule) if component.recurrence_id().is_none() => {
+                let excluded = ical::exdates(component, vtimezones)
+                    .into_iter()
+                    .chain(
+                        component
+                            .uid()
+                            .and_then(|uid| overridden.get(&uid))
+                            .into_iter()
+                            .flatten()
+                            .cloned(),
+                    )
+                    .collect::<Vec<_>>();
+                ical::recurrence_starts(&start, &rrule.value, window_end)
+                    .into_iter()
+                    .filter(|start| {
+                        !excluded
+                            .iter()
+                            .any(|excluded| excluded.timestamp() == start.timestamp())
+                    })
+                    .collect()
+            }
+            _ => vec![start],
+        };
+
+        intervals.extend(starts.into_iter().filter_map(|start| {
+            let end = start.checked_add(duration).ok()?;
+            (end > *window_start && start < *window_end).then_some((start, end))
+        }));
+    }
+    intervals
+}
+
+/// Whether an event makes `own_address` busy.
+fn is_busy(component: &Component, own_address: &str) -> bool {
+    let transparent = component
+        .text("TRANSP")
+        .is_some_and(|transp| transp.eq_ignore_ascii_case("TRANSPARENT"));
+    let cancelled = component
+        .text("STATUS")
+        .is_some_and(|status| status.eq_ignore_ascii_case("CANCELLED"));
+    let declined = component
+        .properties
+        .iter()
+        .filter(|property| property.name == "ATTENDEE")
+        .filter(|property| email_from_address(&property.value).eq_ignore_ascii_case(own_address))
+        .any(|property| {
+            property
+                .param("PARTSTAT")
+                .is_some_and(|partstat| partstat.eq_ignore_ascii_case("DECLINED"))
+        });
+    !transparent && !cancelled && !declined
+}
+
+/// Sort intervals and merge the overlapping or adjacent ones.
+pub(crate) fn merge_intervals(mut intervals: Vec<Interval>) -> Vec<Interval> {
+    intervals.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut merged: Vec<Interval> = Vec::with_capacity(intervals.len());
+    for (start, end) in intervals {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end => {
+                if end > *last_end {
+                    *last_end = end;
+                }
+            }
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::tz::TimeZone;
+
+    use super::*;
+
+    fn zoned(value: &str) -> jiff::Zoned {
+        value
+            .parse::<jiff::Timestamp>()
+            .unwrap()
+            .to_zoned(TimeZone::UTC)
+    }
+
+    fn interval(start: &str, end: &str) -> Interval {
+        (zoned(start), zoned(end))
+    }
+
+    fn utc(intervals: Vec<Interval>) -> Vec<Interval> {
+        intervals
+            .into_iter()
+            .map(|(start, end)| {
+                (
+                    start.with_time_zone(TimeZone::UTC),
+                    end.with_time_zone(TimeZone::UTC),
+                )
+            })
+            .collect()
+    }
+
+    fn vevent(lines: &str) -> Component {
+        Component::parse(&format!("BEGIN:VEVENT\r\n{lines}END:VEVENT\r\n")).unwrap()
+    }
+
+    #[test]
+    fn merges_overlapping_and_adjacent_intervals() {
+        let merged = merge_intervals(vec![
+            interval("2025-01-06T13:00:00Z", "2025-01-06T14:00:00Z"),
+            interval("2025-01-06T09:00:00Z", "2025-01-06T10:00:00Z"),
+            interval("2025-01-06T09:30:00Z", "2025-01-06T10:30:00Z"),
+            interval("2025-01-06T10:30:00Z", "2025-01-06T11:00:00Z"),
+            interval("2025-01-06T09:45:00Z", "2025-01-06T10:00:00Z"),
+        ]);
+        assert_eq!(
+            merged,
+            [
+                interval("2025-01-06T09:00:00Z", "2025-01-06T11:00:00Z"),
+                interval("2025-01-06T13:00:00Z", "2025-01-06T14:00:00Z"),
+            ]
+        );
+        assert_eq!(merge_intervals(Vec::new()), []);
+    }
+
+    #[test]
+    fn reads_vfreebusy_periods() {
+        let vfreebusy = Component::parse(
+            "BEGIN:VFREEBUSY\r\n\
+             ATTENDEE:mailto:jane@example.com\r\n\
+             FREEBUSY:20250106T090000Z/PT1H,20250106T130000Z/20250106T133000Z\r\n\
+             FREEBUSY;FBTYPE=BUSY-TENTATIVE:20250106T150000Z/PT30M\r\n\
+             FREEBUSY;FBTYPE=FREE:20250106T160000Z/PT1H\r\n\
+             END:VFREEBUSY\r\n",
+        )
+        .unwrap();
+
+        let (user, intervals) = vfreebusy_intervals(&vfreebusy);
+        assert_eq!(user.as_deref(), Some("jane@example.com"));
+        assert_eq!(
+            utc(intervals),
+            [
+                interval("2025-01-06T09:00:00Z", "2025-01-06T10:00:00Z"),
+                interval("2025-01-06T13:00:00Z", "2025-01-06T13:30:00Z"),
+                interval("2025-01-06T15:00:00Z", "2025-01-06T15:30:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_events_that_do_not_keep_busy() {
+        let events = [
+            vevent("UID:1\r\nDTSTART:20250106T090000Z\r\nDTEND:20250106T100000Z\r\n"),
+            vevent("UID:2\r\nDTSTART:20250106T110000Z\r\nDURATION:PT30M\r\nTRANSP:TRANSPARENT\r\n"),
+            vevent("UID:3\r\nDTSTART:20250106T120000Z\r\nDURATION:PT30M\r\nSTATUS:CANCELLED\r\n"),
+            vevent(
+                "UID:4\r\nDTSTART:20250106T130000Z\r\nDURATION:PT30M\r\n\
+                 ATTENDEE;PARTSTAT=DECLINED:mailto:Jane@Example.com\r\n",
+            ),
+            vevent(
+                "UID:5\r\nDTSTART:20250106T140000Z\r\nDURATION:PT30M\r\n\
+                 ATTENDEE;PARTSTAT=DECLINED:mailto:bob@example.com\r\n",
+            ),
+            // Outside of the window
+            vevent("UID:6\r\nDTSTART:20250107T090000Z\r\nDURATION:PT1H\r\n"),
+        ];
+        let window = interval("2025-01-06T00:00:00Z", "2025-01-07T00:00:00Z");
+
+        assert_eq!(
+            utc(event_intervals(&events, "jane@example.com", &[], &window)),
+            [
+                interval("2025-01-06T09:00:00Z", "2025-01-06T10:00:00Z"),
+                interval("2025-01-06T14:00:00Z", "2025-01-06T14:30:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn expands_recurring_events() {
+        let events = [
+            vevent(
+                "UID:1\r\nDTSTART:20250106T090000Z\r\nDTEND:20250106T100000Z\r\n\
+                 RRULE:FREQ=DAILY;COUNT=5\r\nEXDATE:20250107T090000Z\r\n",
+            ),
+            // Moves the occurrence of the 8th
+            vevent(
+                "UID:1\r\nRECURRENCE-ID:20250108T090000Z\r\n\
+                 DTSTART:20250108T150000Z\r\nDTEND:20250108T160000Z\r\n",
+            ),
+        ];
+        let window = interval("2025-01-06T12:00:00Z", "2025-01-09T12:00:00Z");
+
+        assert_eq!(
+            utc(event_intervals(&events, "jane@example.com", &[], &window)),
+            [
+                interval("2025-01-09T09:00:00Z", "2025-01-09T10:00:00Z"),
+                interval("2025-01-08T15:00:00Z", "2025-01-08T16:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_durations_across_dst_changes() {
+        // Paris moves to summer time during the night of March 30th
+        let timed = vevent(
+            "UID:1\r\nDTSTART;TZID=Europe/Paris:20250329T200000\r\n\
+             DTEND;TZID=Europe/Paris:20250330T080000\r\nRRULE:FREQ=DAILY;COUNT=2\r\n",
+        );
+        let window = interval("2025-03-29T00:00:00Z", "2025-04-01T00:00:00Z");
+        assert_eq!(
+            utc(event_intervals(&[timed], "jane@example.com", &[], &window)),
+            [
+                interval("2025-03-29T19:00:00Z", "2025-03-30T06:00:00Z"),
+                interval("2025-03-30T18:00:00Z", "2025-03-31T05:00:00Z"),
+            ]
+        );
+
+        let all_day = vevent(
+            "UID:2\r\nDTSTART;VALUE=DATE:20250322\r\nDTEND;VALUE=DATE:20250324\r\n\
+             RRULE:FREQ=WEEKLY;COUNT=2\r\n",
+        );
+        let window = interval("2025-03-01T00:00:00Z", "2025-05-01T00:00:00Z");
+        let intervals = event_intervals(&[all_day], "jane@example.com", &[], &window);
+        let dates = intervals
+            .iter()
+            .map(|(start, end)| (start.datetime(), end.datetime()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dates,
+            [
+                (
+                    jiff::civil::datetime(2025, 3, 22, 0, 0, 0, 0),
+                    jiff::civil::datetime(2025, 3, 24, 0, 0, 0, 0)
+                ),
+                (
+                    jiff::civil::datetime(2025, 3, 29, 0, 0, 0, 0),
+                    jiff::civil::datetime(2025, 3, 31, 0, 0, 0, 0)
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! jCal (RFC 7265) conversion.
+
+use serde_json::{Map, Number, Value};
+
+use super::{
+    Component, Parameter, Property,
+    value::{self, ValueType},
+};
+
+/// The rule parts of a RECUR value holding integers.
+const INTEGER_RECUR_PARTS: &[&str] = &[
+    "count",
+    "interval",
+    "bysecond",
+    "byminute",
+    "byhour",
+    "bymonthday",
+    "byyearday",
+    "byweekno",
+    "bymonth",
+    "bysetpos",
+];
+
+/// Convert a component to jCal.
+pub fn to_jcal(component: &Component) -> Value {
+    Value::Array(vec![
+        Value::String(component.name.to_ascii_lowercase()),
+        Value::Array(component.properties.iter().map(property_to_jcal).collect()),
+        Value::Array(component.components.iter().map(to_jcal).collect()),
+    ])
+}
+
+/// Convert a jCal component, the inverse of [`to_jcal`].
+pub fn from_jcal(jcal: &Value) -> Result<Component, String> {
+    let Some([name, properties, components]) = jcal.as_array().map(Vec::as_slice) else {
+        return Err(format!("invalid jCal component: {jcal}"));
+    };
+    let (Some(name), Some(properties), Some(components)) =
+        (name.as_str(), properties.as_array(), components.as_array())
+    else {
+        return Err(format!("invalid jCal component: {jcal}"));
+    };
+
+    let mut component = Component::new(name);
+    for property in properties {
+        component.properties.push(property_from_jcal(property)?);
+    }
+    for child in components {
+        component.components.push(from_jcal(child)?);
+    }
+    Ok(component)
+}
+
+fn property_to_jcal(property: &Property) -> Value {
+    let ty = ValueType::of(property);
+
+    let mut params = Map::new();
+    for param in &property.params {
+        if param.name.eq_ignore_ascii_case("VALUE") {
+            continue;
+        }
+        let value = match param.values.as_slice() {
+            [value] => Value::String(value.clone()),
+            values => Value::Array(values.iter().cloned().map(Value::String).collect()),
+        };
+        params.insert(param.name.to_ascii_lowercase(), value);
+    }
+
+    let mut jcal = vec![
+        Value::String(property.name.to_ascii_lowercase()),
+        Value::Object(params),
+        Value::String(ty.name().to_owned()),
+    ];
+    let values = value::split_values(property)
+        .into_iter()
+        .map(|raw| value_to_jcal(ty, raw));
+    if is_structured(&property.name) {
+        // The parts of a structured value are a single array, e.g. `[37.38, -122.08]` for GEO
+        jcal.push(Value::Array(values.collect()));
+    } else {
+        jcal.extend(values);
+    }
+    Value::Array(jcal)
+}
+
+fn property_from_jcal(jcal: &Value) -> Result<Property, String> {
+    let invalid = || format!("invalid jCal property: {jcal}");
+    let Some([name, params, ty, values @ ..]) = jcal.as_array().map(Vec::as_slice) else {
+        return Err(invalid());
+    };
+    let (Some(name), Some(params), Some(ty)) = (name.as_str(), params.as_object(), ty.as_str())
+    else {
+        return Err(invalid());
+    };
+
+    let mut property = Property::new(name, "");
+    for (param_name, param_values) in params {
+        let values = match param_values {
+            Value::Array(values) => values.iter().map(scalar_to_string).collect(),
+            value => vec![scalar_to_string(value)],
+        };
+        property.params.push(Parameter {
+            name: param_name.to_ascii_uppercase(),
+            values,
+        });
+    }
+
+    let ty = ValueType::from_name(ty);
+    if ty != ValueType::Unknown && ty != ValueType::default_for(name) {
+        property.set_param("VALUE", &ty.name().to_ascii_uppercase());
+    }
+
+    let values = match values {
+        [Value::Array(parts)] if is_structured(name) => parts.as_slice(),
+        values => values,
+    };
+    let raw_values = values
+        .iter()
+        .map(|value| value_from_jcal(ty, value).ok_or_else(invalid))
+        .collect::<Result<Vec<_>, _>>()?;
+    property.value = value::join_values(name, &raw_values);
+    Ok(property)
+}
+
+/// Whether the values of a property are the parts of a single structured value (RFC 7265
+/// section 3.6.2).
+fn is_structured(property_name: &str) -> bool {
+    property_name.eq_ignore_ascii_case("GEO")
+}
+
+fn value_to_jcal(ty: ValueType, raw: &str) -> Value {
+    match ty {
+        ValueType::Boolean => Value::Bool(raw.eq_ignore_ascii_case("TRUE")),
+        ValueType::Integer => raw
+            .parse::<i64>()
+            .map_or_else(|_| Value::String(raw.to_owned()), Value::from),
+        ValueType::Float => raw
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map_or_else(|| Value::String(raw.to_owned()), Value::Number),
+        ValueType::Recur => {
+            let mut parts = Map::new();
+            for (name, values) in value::recur_parts(raw) {
+                let is_integer = INTEGER_RECUR_PARTS.contains(&name.as_str());
+                let mut values = values
+                    .into_iter()
+                    .map(|value| match value.parse::<i64>() {
+                        Ok(integer) if is_integer => Value::from(integer),
+                        _ => Value::String(value),
+                    })
+                    .collect::<Vec<_>>();
+                let value = if values.len() == 1 {
+                    values.remove(0)
+                } else {
+                    Value::Array(values)
+                };
+                parts.insert(name, value);
+            }
+            Value::Object(parts)
+        }
+        ty => Value::String(value::to_structured(ty, raw)),
+    }
+}
+
+fn value_from_jcal(ty: ValueType, jcal: &Value) -> Option<String> {
+    match jcal {
+        Value::String(value) => Some(value::from_structured(ty, value)),
+        Value::Bool(true) => Some("TRUE".to_owned()),
+        Value::Bool(false) => Some("FALSE".to_owned()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Object(parts) if ty == ValueType::Recur => {
+            let parts = parts
+                .iter()
+                .map(|(name, values)| {
+                    let values = match values {
+                        Value::Array(values) => values.iter().map(scalar_to_string).collect(),
+                        value => vec![scalar_to_string(value)],
+                    };
+                    (name.clone(), values)
+                })
+                .collect::<Vec<_>>();
+            Some(value::recur_from_parts(&parts))
+        }
+        _ => None,
+    }
+}
+
+fn scalar_to_string(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const ICS: &str = "BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:1\r\n\
+        DTSTART;TZID=Europe/Paris:20250101T100000\r\n\
+        DURATION:PT1H\r\n\
+        SUMMARY:Lunch\\, then coffee\r\n\
+        GEO:37.386013;-122.082932\r\n\
+        CATEGORIES:Food,Work\r\n\
+        RRULE:FREQ=WEEKLY;COUNT=5;BYDAY=MO,WE\r\n\
+        EXDATE;VALUE=DATE:20250106\r\n\
+        SEQUENCE:2\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn converts_properties() {
+        let jcal = to_jcal(&Component::parse(ICS).unwrap());
+        let properties = &jcal[2][0][1];
+        assert_eq!(
+            properties[1],
+            json!(["dtstart", {"tzid": "Europe/Paris"}, "date-time", "2025-01-01T10:00:00"])
+        );
+        assert_eq!(
+            properties[3],
+            json!(["summary", {}, "text", "Lunch, then coffee"])
+        );
+        assert_eq!(
+            properties[4],
+            json!(["geo", {}, "float", [37.386013, -122.082932]])
+        );
+        assert_eq!(
+            properties[5],
+            json!(["categories", {}, "text", "Food", "Work"])
+        );
+        assert_eq!(
+            properties[6],
+            json!(["rrule", {}, "recur", {"freq": "WEEKLY", "count": 5, "byday": ["MO", "WE"]}])
+        );
+        assert_eq!(properties[7], json!(["exdate", {}, "date", "2025-01-06"]));
+        assert_eq!(properties[8], json!(["sequence", {}, "integer", 2]));
+    }
+
+    #[test]
+    fn round_trips() {
+        let component = Component::parse(ICS).unwrap();
+        let jcal = to_jcal(&component);
+        assert_eq!(from_jcal(&jcal).unwrap(), component);
+        assert_eq!(from_jcal(&jcal).unwrap().to_string(), ICS);
+    }
+
+    #[test]
+    fn rejects_invalid_components() {
+        assert!(from_jcal(&json!(["vevent", []])).is_err());
+        assert!(from_jcal(&json!(["vevent", [["uid"]], []])).is_err());
+    }
+}
@@ -0,0 +1,468 @@
+//! Minimal iCalendar (RFC 5545) object model, used to talk to EDS.
+
+use std::fmt;
+
+mod jcal;
+mod recur;
+mod time;
+mod value;
+mod xcal;
+
+pub use self::{jcal::*, recur::*, time::*, value::*, xcal::*};
+
+/// A parameter of a property, e.g. `TZID=Europe/Paris`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+/// A content line of a component, e.g. `DTSTART;TZID=Europe/Paris:20250101T100000`.
+///
+/// The value is kept in its escaped form, see [`Property::text_value`] for text values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Property {
+    pub name: String,
+    pub params: Vec<Parameter>,
+    pub value: String,
+}
+
+impl Property {
+    /// Create a property with a raw value.
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_ascii_uppercase(),
+            params: Vec::new(),
+            value: value.to_owned(),
+        }
+    }
+
+    /// Create a property with a text value, escaping it.
+    pub fn text(name: &str, text: &str) -> Self {
+        Self::new(name, &escape_text(text))
+    }
+
+    /// The unescaped value of a text property.
+    pub fn text_value(&self) -> String {
+        unescape_text(&self.value)
+    }
+
+    /// The first value of the given parameter.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|param| param.name.eq_ignore_ascii_case(name))
+            .and_then(|param| param.values.first())
+            .map(String::as_str)
+    }
+
+    /// Set a single-valued parameter, replacing any previous value.
+    pub fn set_param(&mut self, name: &str, value: &str) {
+        self.remove_param(name);
+        self.params.push(Parameter {
+            name: name.to_ascii_uppercase(),
+            values: vec![value.to_owned()],
+        });
+    }
+
+    /// Set a single-valued parameter, consuming the property.
+    pub fn with_param(mut self, name: &str, value: &str) -> Self {
+        self.set_param(name, value);
+        self
+    }
+
+    /// Remove the given parameter.
+    pub fn remove_param(&mut self, name: &str) {
+        self.params
+            .retain(|param| !param.name.eq_ignore_ascii_case(name));
+    }
+}
+
+/// A component, e.g. `VCALENDAR`, `VEVENT` or `VTIMEZONE`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Component {
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub components: Vec<Component>,
+}
+
+impl Component {
+    /// Create an empty component.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_ascii_uppercase(),
+            ..Default::default()
+        }
+    }
+
+    /// Parse a single component, which may be wrapped in a `VCALENDAR`.
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut lines = unfold(input).into_iter().filter(|line| !line.is_empty());
+        let Some(first) = lines.next() else {
+            return Err(ParseError::Empty);
+        };
+        let first = parse_line(&first)?;
+        if first.name != "BEGIN" {
+            return Err(ParseError::UnexpectedLine(first.name));
+        }
+
+        let component = parse_component(&first.value.to_ascii_uppercase(), &mut lines)?;
+        match lines.next() {
+            Some(line) => Err(ParseError::UnexpectedLine(line)),
+            None => Ok(component),
+        }
+    }
+
+    /// The first property with the given name.
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The unescaped value of the first text property with the given name.
+    pub fn text(&self, name: &str) -> Option<String> {
+        self.property(name).map(Property::text_value)
+    }
+
+    /// Replace all the properties named like `property` with it.
+    pub fn set_property(&mut self, property: Property) {
+        self.remove_properties(&property.name);
+        self.properties.push(property);
+    }
+
+    /// Set a text property, or remove it if `text` is empty.
+    pub fn set_text(&mut self, name: &str, text: &str) {
+        if text.is_empty() {
+            self.remove_properties(name);
+        } else {
+            self.set_property(Property::text(name, text));
+        }
+    }
+
+    /// The unescaped values of all the multi-valued text properties with the given name, e.g.
+    /// CATEGORIES.
+    pub fn text_values(&self, name: &str) -> Vec<String> {
+        self.properties
+            .iter()
+            .filter(|property| property.name.eq_ignore_ascii_case(name))
+            .flat_map(split_values)
+            .map(unescape_text)
+            .filter(|value| !value.is_empty())
+            .collect()
+    }
+
+    /// Replace all the properties with the given name with a single multi-valued text property,
+    /// or remove them if `values` is empty.
+    pub fn set_text_values(&mut self, name: &str, values: &[String]) {
+        self.remove_properties(name);
+        let values = values
+            .iter()
+            .map(|value| escape_text(value))
+            .collect::<Vec<_>>();
+        if !values.is_empty() {
+            self.properties
+                .push(Property::new(name, &join_values(name, &values)));
+        }
+    }
+
+    /// Remove all the properties with the given name.
+    pub fn remove_properties(&mut self, name: &str) {
+        self.properties
+            .retain(|property| !property.name.eq_ignore_ascii_case(name));
+    }
+
+    /// The TZIDs referenced by the properties of this component.
+    pub fn tzids(&self) -> impl Iterator<Item = &str> {
+        self.properties
+            .iter()
+            .filter_map(|property| property.param("TZID"))
+    }
+
+    /// The sub-components with the given name.
+    pub fn components_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Component> {
+        self.components
+            .iter()
+            .filter(move |component| component.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The UID of this component.
+    pub fn uid(&self) -> Option<String> {
+        self.text("UID")
+    }
+
+    /// The RECURRENCE-ID of this component, if it is a detached instance.
+    pub fn recurrence_id(&self) -> Option<&str> {
+        self.property("RECURRENCE-ID")
+            .map(|property| property.value.as_str())
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_folded(f, &format!("BEGIN:{}", self.name))?;
+        for property in &self.properties {
+            let mut line = property.name.clone();
+            for param in &property.params {
+                line.push(';');
+                line.push_str(&param.name);
+                line.push('=');
+                let values = param
+                    .values
+                    .iter()
+                    .map(|value| quote_param(value))
+                    .collect::<Vec<_>>();
+                line.push_str(&values.join(","));
+            }
+            line.push(':');
+            line.push_str(&property.value);
+            write_folded(f, &line)?;
+        }
+        for component in &self.components {
+            write!(f, "{component}")?;
+        }
+        write_folded(f, &format!("END:{}", self.name))
+    }
+}
+
+/// An error encountered while parsing iCalendar data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    InvalidLine(String),
+    UnexpectedLine(String),
+    UnterminatedComponent(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no component found"),
+            Self::InvalidLine(line) => write!(f, "invalid content line: {line}"),
+            Self::UnexpectedLine(line) => write!(f, "unexpected content line: {line}"),
+            Self::UnterminatedComponent(name) => write!(f, "component {name} is not terminated"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn parse_component(
+    name: &str,
+    lines: &mut impl Iterator<Item = String>,
+) -> Result<Component, ParseError> {
+    let mut component = Component::new(name);
+    while let Some(line) = lines.next() {
+        let property = parse_line(&line)?;
+        match property.name.as_str() {
+            "BEGIN" => {
+                let child = parse_component(&property.value.to_ascii_uppercase(), lines)?;
+                component.components.push(child);
+            }
+            "END" if property.value.eq_ignore_ascii_case(name) => return Ok(component),
+            "END" => return Err(ParseError::UnexpectedLine(line)),
+            _ => component.properties.push(property),
+        }
+    }
+    Err(ParseError::UnterminatedComponent(name.to_owned()))
+}
+
+/// Unfold content lines, joining continuation lines starting with a space or a tab.
+fn unfold(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in input.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+    lines
+}
+
+fn parse_line(line: &str) -> Result<Property, ParseError> {
+    let invalid = || ParseError::InvalidLine(line.to_owned());
+
+    let name_end = line.find([';', ':']).ok_or_else(invalid)?;
+    let name = &line[..name_end];
+    if name.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut params = Vec::new();
+    let mut rest = &line[name_end..];
+    while let Some(param) = rest.strip_prefix(';') {
+        let eq = param.find('=').ok_or_else(invalid)?;
+        let param_name = &param[..eq];
+        let mut values = Vec::new();
+        let mut remaining = &param[eq + 1..];
+        loop {
+            let (value, after) = if let Some(quoted) = remaining.strip_prefix('"') {
+                let end = quoted.find('"').ok_or_else(invalid)?;
+                (&quoted[..end], &quoted[end + 1..])
+            } else {
+                let end = remaining.find([',', ';', ':']).ok_or_else(invalid)?;
+                (&remaining[..end], &remaining[end..])
+            };
+            values.push(value.to_owned());
+            match after.strip_prefix(',') {
+                Some(after) => remaining = after,
+                None => {
+                    remaining = after;
+                    break;
+                }
+            }
+        }
+        params.push(Parameter {
+            name: param_name.to_ascii_uppercase(),
+            values,
+        });
+        rest = remaining;
+    }
+
+    let value = rest.strip_prefix(':').ok_or_else(invalid)?;
+    Ok(Property {
+        name: name.to_ascii_uppercase(),
+        params,
+        value: value.to_owned(),
+    })
+}
+
+/// Write a content line, folding it at 75 octets.
+fn write_folded(f: &mut fmt::Formatter<'_>, line: &str) -> fmt::Result {
+    let mut start = 0;
+    let mut limit = 75;
+    while line.len() - start > limit {
+        let mut end = start + limit;
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        write!(f, "{}\r\n ", &line[start..end])?;
+        start = end;
+        // Account for the leading space of continuation lines
+        limit = 74;
+    }
+    write!(f, "{}\r\n", &line[start..])
+}
+
+fn quote_param(value: &str) -> String {
+    if value.contains([':', ';', ',']) {
+        format!("\"{}\"", value.replace('"', ""))
+    } else {
+        value.to_owned()
+    }
+}
+
+/// Escape a TEXT value.
+pub fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Unescape a TEXT value.
+pub fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(c) => text.push(c),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unfolds_continuation_lines() {
+        let lines = unfold("SUMMARY:Long\r\n  summary\r\n\tcontinued\r\nUID:1\r\n");
+        assert_eq!(lines, ["SUMMARY:Long summarycontinued", "UID:1", ""]);
+    }
+
+    #[test]
+    fn folds_long_lines_at_75_octets() {
+        let mut component = Component::new("VEVENT");
+        component.set_text("SUMMARY", &"é".repeat(100));
+        let output = component.to_string();
+
+        for line in output.split("\r\n") {
+            assert!(line.len() <= 75, "line too long: {line:?}");
+        }
+        assert_eq!(Component::parse(&output).unwrap(), component);
+    }
+
+    #[test]
+    fn escapes_text() {
+        let text = "a, b; c\\d\ne";
+        assert_eq!(escape_text(text), r"a\, b\; c\\d\ne");
+        assert_eq!(unescape_text(&escape_text(text)), text);
+        assert_eq!(unescape_text("line\\Nbreak"), "line\nbreak");
+    }
+
+    #[test]
+    fn parses_parameters() {
+        let component = Component::parse(
+            "BEGIN:VEVENT\r\n\
+             ATTENDEE;CN=\"Doe, Jane\";MEMBER=\"mailto:a@x\",\"mailto:b@x\":mailto:jane@x\r\n\
+             END:VEVENT\r\n",
+        )
+        .unwrap();
+        let attendee = component.property("attendee").unwrap();
+        assert_eq!(attendee.param("cn"), Some("Doe, Jane"));
+        assert_eq!(
+            attendee.params[1].values,
+            ["mailto:a@x".to_string(), "mailto:b@x".to_string()]
+        );
+        assert_eq!(attendee.value, "mailto:jane@x");
+
+        let output = component.to_string();
+        assert_eq!(Component::parse(&output).unwrap(), component);
+    }
+
+    #[test]
+    fn parses_nested_components() {
+        let component =
+            Component::parse("BEGIN:VCALENDAR\nBEGIN:VEVENT\nUID:1\nEND:VEVENT\nEND:VCALENDAR\n")
+                .unwrap();
+        assert_eq!(component.name, "VCALENDAR");
+        assert_eq!(
+            component.components_named("VEVENT").next().unwrap().uid(),
+            Some("1".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert_eq!(Component::parse(""), Err(ParseError::Empty));
+        assert_eq!(
+            Component::parse("BEGIN:VEVENT\nUID:1\n"),
+            Err(ParseError::UnterminatedComponent("VEVENT".to_string()))
+        );
+        assert_eq!(
+            Component::parse("BEGIN:VEVENT\nEND:VTODO\n"),
+            Err(ParseError::UnexpectedLine("END:VTODO".to_string()))
+        );
+        assert!(matches!(
+            Component::parse("BEGIN:VEVENT\nNO COLON\nEND:VEVENT\n"),
+            Err(ParseError::InvalidLine(_))
+        ));
+    }
+}
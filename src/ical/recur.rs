@@ -0,0 +1,563 @@
+//! Recurrence rule (RRULE) expansion.
+
+use jiff::civil::{Date, Weekday};
+
+use super::{Component, Property, parse_date_time, split_values};
+
+/// The maximum number of periods of a rule to go through, to bound the expansion of rules
+/// starting long before the requested window.
+const MAX_PERIODS: i64 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The start of the occurrences of a rule, from `dtstart` included until `window_end` excluded.
+///
+/// FREQ from DAILY to YEARLY is supported with INTERVAL, COUNT, UNTIL, BYDAY, BYMONTHDAY,
+/// BYMONTH, BYSETPOS and WKST. Other rule parts are ignored.
+pub fn recurrence_starts(
+    dtstart: &jiff::Zoned,
+    rrule: &str,
+    window_end: &jiff::Zoned,
+) -> Vec<jiff::Zoned> {
+    let mut frequency = None;
+    let mut interval = 1;
+    let mut count = None;
+    let mut until = None;
+    let mut by_day = Vec::new();
+    let mut by_month_day = Vec::new();
+    let mut by_month = Vec::new();
+    let mut by_set_pos = Vec::new();
+    let mut week_start_day = Weekday::Monday;
+
+    for (name, value) in rrule.split(';').filter_map(|part| part.split_once('=')) {
+        let numbers = || {
+            value
+                .split(',')
+                .filter_map(|value| value.parse::<i8>().ok())
+        };
+        match name.to_ascii_uppercase().as_str() {
+            "FREQ" => {
+                frequency = match value.to_ascii_uppercase().as_str() {
+                    "DAILY" => Some(Frequency::Daily),
+                    "WEEKLY" => Some(Frequency::Weekly),
+                    "MONTHLY" => Some(Frequency::Monthly),
+                    "YEARLY" => Some(Frequency::Yearly),
+                    _ => None,
+                }
+            }
+            "INTERVAL" => interval = value.parse::<i64>().unwrap_or(1).max(1),
+            "COUNT" => count = value.parse::<usize>().ok(),
+            "UNTIL" => until = parse_date_time(&Property::new("UNTIL", value), &[]),
+            "BYDAY" => by_day = value.split(',').filter_map(parse_weekday).collect(),
+            "BYMONTHDAY" => by_month_day = numbers().collect(),
+            "BYMONTH" => by_month = numbers().collect(),
+            "BYSETPOS" => {
+                by_set_pos = value
+                    .split(',')
+                    .filter_map(|value| value.parse::<i16>().ok())
+                    .collect()
+            }
+            "WKST" => {
+                if let Some((None, day)) = parse_weekday(value) {
+                    week_start_day = day;
+                }
+            }
+            _ => {}
+        }
+    }
+    let Some(frequency) = frequency else {
+        return vec![dtstart.clone()];
+    };
+
+    let time_zone = dtstart.time_zone().clone();
+    let first_date = dtstart.date();
+    let time = dtstart.time();
+    let mut starts = Vec::new();
+
+    for period in 0..MAX_PERIODS {
+        let offset = period * interval;
+        let mut dates: Vec<Date> = match frequency {
+            Frequency::Daily => first_date
+                .checked_add(jiff::Span::new().days(offset))
+                .into_iter()
+                .filter(|date| {
+                    by_day.is_empty() || by_day.iter().any(|(_, day)| *day == date.weekday())
+                })
+                .collect(),
+            Frequency::Weekly => {
+                // Weeks start on WKST, which decides the days of a week with an INTERVAL
+                let week_start = first_date
+                    .checked_sub(jiff::Span::new().days(first_date.weekday().since(week_start_day)))
+                    .and_then(|date| date.checked_add(jiff::Span::new().weeks(offset)));
+                let Ok(week_start) = week_start else {
+                    break;
+                };
+                if by_day.is_empty() {
+                    week_start
+                        .checked_add(
+                            jiff::Span::new().days(first_date.weekday().since(week_start_day)),
+                        )
+                        .into_iter()
+                        .collect()
+                } else {
+                    let mut dates = by_day
+                        .iter()
+                        .filter_map(|(_, day)| {
+                            week_start
+                                .checked_add(jiff::Span::new().days(day.since(week_start_day)))
+                                .ok()
+                        })
+                        .collect::<Vec<_>>();
+                    dates.sort();
+                    dates
+                }
+            }
+            Frequency::Monthly => {
+                let Some((year, month)) = add_months(first_date, offset) else {
+                    break;
+                };
+                month_dates(year, month, first_date.day(), &by_day, &by_month_day)
+            }
+            Frequency::Yearly => {
+                let Ok(year) = i16::try_from(i64::from(first_date.year()) + offset) else {
+                    break;
+                };
+                let months = if by_month.is_empty() {
+                    vec![first_date.month()]
+                } else {
+                    by_month.clone()
+                };
+                let mut dates = months
+                    .into_iter()
+                    .flat_map(|month| {
+                        month_dates(year, month, first_date.day(), &by_day, &by_month_day)
+                    })
+                    .collect::<Vec<_>>();
+                dates.sort();
+                dates
+            }
+        };
+
+        if !by_month.is_empty() && frequency != Frequency::Yearly {
+            dates.retain(|date| by_month.contains(&date.month()));
+        }
+
+        for date in set_positions(dates, &by_set_pos) {
+            let Ok(start) = date.to_datetime(time).to_zoned(time_zone.clone()) else {
+                continue;
+            };
+            if start < *dtstart {
+                continue;
+            }
+            let after_until = until.as_ref().is_some_and(|(until, is_date)| {
+                if *is_date {
+                    start.date() > until.date()
+                } else {
+                    start > *until
+                }
+            });
+            if after_until
+                || start >= *window_end
+                || count.is_some_and(|count| starts.len() >= count)
+            {
+                return starts;
+            }
+            starts.push(start);
+        }
+    }
+
+    starts
+}
+
+/// The dates excluded from the recurrence of a component by its EXDATE properties.
+pub fn exdates(component: &Component, vtimezones: &[Component]) -> Vec<jiff::Zoned> {
+    component
+        .properties
+        .iter()
+        .filter(|property| property.name == "EXDATE")
+        .flat_map(|property| {
+            split_values(property)
+                .into_iter()
+                .filter_map(|value| {
+                    let mut exdate = property.clone();
+                    exdate.value = value.to_string();
+                    parse_date_time(&exdate, vtimezones)
+                })
+                .map(|(exdate, _)| exdate)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Parse a BYDAY value, e.g. `MO`, `2TU` or `-1FR`.
+fn parse_weekday(value: &str) -> Option<(Option<i8>, Weekday)> {
+    let split = value.len().checked_sub(2)?;
+    let (ordinal, day) = value.split_at_checked(split)?;
+    let day = match day.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Monday,
+        "TU" => Weekday::Tuesday,
+        "WE" => Weekday::Wednesday,
+        "TH" => Weekday::Thursday,
+        "FR" => Weekday::Friday,
+        "SA" => Weekday::Saturday,
+        "SU" => Weekday::Sunday,
+        _ => return None,
+    };
+    let ordinal = match ordinal {
+        "" => None,
+        ordinal => Some(ordinal.trim_start_matches('+').parse().ok()?),
+    };
+    Some((ordinal, day))
+}
+
+/// The year and month `months` months after the month of `date`.
+fn add_months(date: Date, months: i64) -> Option<(i16, i8)> {
+    let total = i64::from(date.year()) * 12 + i64::from(date.month()) - 1 + months;
+    let year = i16::try_from(total.div_euclid(12)).ok()?;
+    let month = i8::try_from(total.rem_euclid(12) + 1).ok()?;
+    Some((year, month))
+}
+
+/// The dates of a month selected by BYDAY or BYMONTHDAY, or the given day of the month.
+fn month_dates(
+    year: i16,
+    month: i8,
+    day: i8,
+    by_day: &[(Option<i8>, Weekday)],
+    by_month_day: &[i8],
+) -> Vec<Date> {
+    let Ok(first) = Date::new(year, month, 1) else {
+        return Vec::new();
+    };
+    let days_in_month = first.days_in_month();
+    let days = (1..=days_in_month)
+        .filter_map(|day| Date::new(year, month, day).ok())
+        .collect::<Vec<_>>();
+
+    let mut dates = if !by_day.is_empty() {
+        by_day
+            .iter()
+            .flat_map(|(ordinal, weekday)| {
+                let matching = days
+                    .iter()
+                    .copied()
+                    .filter(|date| date.weekday() == *weekday)
+                    .collect::<Vec<_>>();
+                match ordinal {
+                    None => matching,
+                    Some(ordinal) => {
+                        let index = if *ordinal > 0 {
+                            usize::try_from(*ordinal - 1).ok()
+                        } else {
+                            matching
+                                .len()
+                                .checked_sub(usize::from(ordinal.unsigned_abs()))
+                        };
+                        index
+                            .and_then(|index| matching.get(index).copied())
+                            .into_iter()
+                            .collect()
+                    }
+                }
+            })
+            .filter(|date| {
+                by_month_day.is_empty()
+                    || by_month_day
+                        .iter()
+                        .any(|day| *day == date.day() || *day == date.day() - days_in_month - 1)
+            })
+            .collect::<Vec<_>>()
+    } else if !by_month_day.is_empty() {
+        by_month_day
+            .iter()
+            .filter_map(|day| {
+                let day = if *day < 0 {
+                    days_in_month + 1 + *day
+                } else {
+                    *day
+                };
+                Date::new(year, month, day).ok()
+            })
+            .collect()
+    } else {
+        Date::new(year, month, day).ok().into_iter().collect()
+    };
+    dates.sort();
+    dates.dedup();
+    dates
+}
+
+/// The dates at the BYSETPOS positions among the sorted dates of a period, from 1 for the first
+/// and from -1 for the last, or all of them without positions.
+fn set_positions(dates: Vec<Date>, positions: &[i16]) -> Vec<Date> {
+    if positions.is_empty() {
+        return dates;
+    }
+
+    let mut selected = positions
+        .iter()
+        .filter_map(|position| {
+            let index = if *position > 0 {
+                usize::from(position.unsigned_abs()) - 1
+            } else {
+                dates
+                    .len()
+                    .checked_sub(usize::from(position.unsigned_abs()))?
+            };
+            dates.get(index).copied()
+        })
+        .collect::<Vec<_>>();
+    selected.sort();
+    selected.dedup();
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zoned(value: &str) -> jiff::Zoned {
+        value.parse().unwrap()
+    }
+
+    /// The local dates and times of the occurrences of a rule, in 2025 unless it ends earlier.
+    fn starts(dtstart: &str, rrule: &str) -> Vec<String> {
+        recurrence_starts(
+            &zoned(dtstart),
+            rrule,
+            &zoned("2026-01-01T00:00:00[Europe/Paris]"),
+        )
+        .into_iter()
+        .map(|start| start.datetime().to_string())
+        .collect()
+    }
+
+    #[test]
+    fn stops_after_count() {
+        assert_eq!(
+            starts("2025-01-01T10:00:00[Europe/Paris]", "FREQ=DAILY;COUNT=3"),
+            [
+                "2025-01-01T10:00:00",
+                "2025-01-02T10:00:00",
+                "2025-01-03T10:00:00"
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_after_until() {
+        // UNTIL is inclusive, and compared in UTC
+        assert_eq!(
+            starts(
+                "2025-01-01T10:00:00[Europe/Paris]",
+                "FREQ=WEEKLY;UNTIL=20250115T090000Z"
+            ),
+            [
+                "2025-01-01T10:00:00",
+                "2025-01-08T10:00:00",
+                "2025-01-15T10:00:00"
+            ]
+        );
+        assert_eq!(
+            starts(
+                "2025-01-01T10:00:00[Europe/Paris]",
+                "FREQ=MONTHLY;UNTIL=20250301"
+            ),
+            [
+                "2025-01-01T10:00:00",
+                "2025-02-01T10:00:00",
+                "2025-03-01T10:00:00"
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_at_window_end() {
+        let starts = recurrence_starts(
+            &zoned("2025-01-01T10:00:00[Europe/Paris]"),
+            "FREQ=DAILY",
+            &zoned("2025-01-03T10:00:00[Europe/Paris]"),
+        );
+        assert_eq!(starts.len(), 2);
+    }
+
+    #[test]
+    fn selects_ordinal_weekdays() {
+        assert_eq!(
+            starts(
+                "2025-01-14T09:00:00[Europe/Paris]",
+                "FREQ=MONTHLY;BYDAY=2TU;COUNT=3"
+            ),
+            [
+                "2025-01-14T09:00:00",
+                "2025-02-11T09:00:00",
+                "2025-03-11T09:00:00"
+            ]
+        );
+        assert_eq!(
+            starts(
+                "2025-01-31T09:00:00[Europe/Paris]",
+                "FREQ=MONTHLY;BYDAY=-1FR;COUNT=3"
+            ),
+            [
+                "2025-01-31T09:00:00",
+                "2025-02-28T09:00:00",
+                "2025-03-28T09:00:00"
+            ]
+        );
+    }
+
+    #[test]
+    fn selects_last_day_of_month() {
+        assert_eq!(
+            starts(
+                "2024-01-31T09:00:00[Europe/Paris]",
+                "FREQ=MONTHLY;BYMONTHDAY=-1;COUNT=3"
+            ),
+            [
+                "2024-01-31T09:00:00",
+                "2024-02-29T09:00:00",
+                "2024-03-31T09:00:00"
+            ]
+        );
+        // Friday the 13th
+        assert_eq!(
+            starts(
+                "2024-09-13T09:00:00[Europe/Paris]",
+                "FREQ=MONTHLY;BYDAY=FR;BYMONTHDAY=13;COUNT=2"
+            ),
+            ["2024-09-13T09:00:00", "2024-12-13T09:00:00"]
+        );
+    }
+
+    #[test]
+    fn selects_set_positions() {
+        // The last weekday of the month
+        assert_eq!(
+            starts(
+                "2025-01-31T09:00:00[Europe/Paris]",
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1;COUNT=3"
+            ),
+            [
+                "2025-01-31T09:00:00",
+                "2025-02-28T09:00:00",
+                "2025-03-31T09:00:00"
+            ]
+        );
+        // The first and last Monday of March
+        assert_eq!(
+            starts(
+                "2025-03-03T09:00:00[Europe/Paris]",
+                "FREQ=YEARLY;BYMONTH=3;BYDAY=MO;BYSETPOS=1,-1;COUNT=2"
+            ),
+            ["2025-03-03T09:00:00", "2025-03-31T09:00:00"]
+        );
+    }
+
+    #[test]
+    fn uses_week_start() {
+        // RFC 5545 section 3.8.5.3
+        let dtstart = "1997-08-05T09:00:00[America/New_York]";
+        assert_eq!(
+            starts(
+                dtstart,
+                "FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=MO"
+            ),
+            [
+                "1997-08-05T09:00:00",
+                "1997-08-10T09:00:00",
+                "1997-08-19T09:00:00",
+                "1997-08-24T09:00:00"
+            ]
+        );
+        assert_eq!(
+            starts(
+                dtstart,
+                "FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=SU"
+            ),
+            [
+                "1997-08-05T09:00:00",
+                "1997-08-17T09:00:00",
+                "1997-08-19T09:00:00",
+                "1997-08-31T09:00:00"
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_local_time_across_dst() {
+        let starts = recurrence_starts(
+            &zoned("2025-03-29T10:00:00[Europe/Paris]"),
+            "FREQ=DAILY;COUNT=3",
+            &zoned("2026-01-01T00:00:00[Europe/Paris]"),
+        );
+        assert_eq!(
+            starts.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "2025-03-29T10:00:00+01:00[Europe/Paris]",
+                "2025-03-30T10:00:00+02:00[Europe/Paris]",
+                "2025-03-31T10:00:00+02:00[Europe/Paris]"
+            ]
+        );
+
+        // Times skipped by the transition are moved forward
+        assert_eq!(
+            self::starts("2025-03-29T02:30:00[Europe/Paris]", "FREQ=DAILY;COUNT=3"),
+            [
+                "2025-03-29T02:30:00",
+                "2025-03-30T03:30:00",
+                "2025-03-31T02:30:00"
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_exdates() {
+        let component = Component::parse(
+            "BEGIN:VEVENT\r\n\
+             DTSTART;TZID=Europe/Paris:20250101T100000\r\n\
+             RRULE:FREQ=DAILY;COUNT=4\r\n\
+             EXDATE;TZID=Europe/Paris:20250102T100000,20250103T100000\r\n\
+             END:VEVENT\r\n",
+        )
+        .unwrap();
+        let excluded = exdates(&component, &[]);
+        assert_eq!(
+            excluded,
+            [
+                zoned("2025-01-02T10:00:00[Europe/Paris]"),
+                zoned("2025-01-03T10:00:00[Europe/Paris]")
+            ]
+        );
+
+        let starts = recurrence_starts(
+            &zoned("2025-01-01T10:00:00[Europe/Paris]"),
+            &component.property("RRULE").unwrap().value,
+            &zoned("2026-01-01T00:00:00[Europe/Paris]"),
+        )
+        .into_iter()
+        .filter(|start| !excluded.contains(start))
+        .collect::<Vec<_>>();
+        assert_eq!(
+            starts,
+            [
+                zoned("2025-01-01T10:00:00[Europe/Paris]"),
+                zoned("2025-01-04T10:00:00[Europe/Paris]")
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_rules_without_frequency() {
+        assert_eq!(
+            starts("2025-01-01T10:00:00[Europe/Paris]", "COUNT=3"),
+            ["2025-01-01T10:00:00"]
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use jiff::{
+    Span,
+    civil::{Date, DateTime},
+    tz::{Offset, TimeZone},
+};
+
+use super::{Component, Property};
+
+const LIBICAL_TZID_PREFIX: &str = "/freeassociation.sourceforge.net/";
+
+/// Resolve a TZID to a time zone.
+///
+/// IANA names, including the ones prefixed by libical, are looked up in the time zone database.
+/// Other TZIDs are resolved with the matching VTIMEZONE definition, if any.
+pub fn resolve_tzid(tzid: &str, vtimezones: &[Component]) -> Option<TimeZone> {
+    let name = tzid
+        .strip_prefix(LIBICAL_TZID_PREFIX)
+        .map(|name| name.strip_prefix("Tzfile/").unwrap_or(name))
+        .unwrap_or(tzid);
+    if let Ok(tz) = TimeZone::get(name) {
+        return Some(tz);
+    }
+
+    let vtimezone = vtimezones
+        .iter()
+        .find(|vtimezone| vtimezone.property("TZID").is_some_and(|p| p.value == tzid))?;
+    resolve_vtimezone(vtimezone)
+}
+
+/// Resolve a VTIMEZONE definition to a time zone.
+///
+/// Definitions carrying their IANA name in X-LIC-LOCATION are looked up in the time zone
+/// database, the others fall back to the offset of their most recent standard observance.
+fn resolve_vtimezone(vtimezone: &Component) -> Option<TimeZone> {
+    if let Some(location) = vtimezone.property("X-LIC-LOCATION")
+        && let Ok(tz) = TimeZone::get(&location.value)
+    {
+        return Some(tz);
+    }
+
+    let standard = vtimezone
+        .components_named("STANDARD")
+        .max_by_key(|observance| {
+            observance
+                .property("DTSTART")
+                .map(|dtstart| dtstart.value.clone())
+        })
+        .or_else(|| vtimezone.components_named("DAYLIGHT").next())?;
+    let offset = parse_utc_offset(&standard.property("TZOFFSETTO")?.value)?;
+    Some(TimeZone::fixed(offset))
+}
+
+/// Parse a UTC-OFFSET value, e.g. `+0100` or `-043000`.
+fn parse_utc_offset(value: &str) -> Option<Offset> {
+    let (sign, digits) = match value.split_at_checked(1)? {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return None,
+    };
+    if !matches!(digits.len(), 4 | 6) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[0..2].parse().ok()?;
+    let minutes: i32 = digits[2..4].parse().ok()?;
+    let seconds: i32 = digits.get(4..6).map_or(Some(0), |s| s.parse().ok())?;
+    Offset::from_seconds(sign * (hours * 3600 + minutes * 60 + seconds)).ok()
+}
+
+/// Parse a DATE or DATE-TIME property, e.g. DTSTART.
+///
+/// Returns the parsed time and whether the value was a date. Dates and floating times are not
+/// tied to an instant, so they are interpreted in the system time zone.
+pub fn parse_date_time(
+    property: &Property,
+    vtimezones: &[Component],
+) -> Option<(jiff::Zoned, bool)> {
+    let value = property.value.trim();
+    let is_date = property
+        .param("VALUE")
+        .is_some_and(|v| v.eq_ignore_ascii_case("DATE"))
+        || value.len() == 8;
+
+    if is_date {
+        let date = Date::strptime("%Y%m%d", value).ok()?;
+        return date
+            .to_zoned(TimeZone::system())
+            .ok()
+            .map(|zoned| (zoned, true));
+    }
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let datetime = DateTime::strptime("%Y%m%dT%H%M%S", utc).ok()?;
+        return datetime
+            .to_zoned(TimeZone::UTC)
+            .ok()
+            .map(|zoned| (zoned, false));
+    }
+
+    let datetime = DateTime::strptime("%Y%m%dT%H%M%S", value).ok()?;
+    let tz = match property.param("TZID") {
+        Some(tzid) => resolve_tzid(tzid, vtimezones)?,
+        None => TimeZone::system(),
+    };
+    datetime.to_zoned(tz).ok().map(|zoned| (zoned, false))
+}
+
+/// Parse a DURATION value, e.g. `PT1H30M` or `-P1D`.
+pub fn parse_duration(value: &str) -> Option<Span> {
+    value.trim().parse().ok()
+}
+
+/// Create a DATE or DATE-TIME property, e.g. DTSTART.
+///
+/// Times in a named time zone keep it as TZID, other times are written in UTC.
+pub fn date_time_property(name: &str, zoned: &jiff::Zoned, is_date: bool) -> Property {
+    if is_date {
+        return Property::new(name, &zoned.date().strftime("%Y%m%d").to_string())
+            .with_param("VALUE", "DATE");
+    }
+
+    match zoned.time_zone().iana_name() {
+        Some(tzid) if tzid != "UTC" => Property::new(
+            name,
+            &zoned.datetime().strftime("%Y%m%dT%H%M%S").to_string(),
+        )
+        .with_param("TZID", tzid),
+        _ => Property::new(
+            name,
+            &zoned.timestamp().strftime("%Y%m%dT%H%M%SZ").to_string(),
+        ),
+    }
+}
+
+/// The value of an INTEGER property of a component, e.g. PRIORITY, or 0.
+pub fn integer(component: &Component, name: &str) -> u32 {
+    component
+        .property(name)
+        .and_then(|property| property.value.trim().parse().ok())
+        .unwrap_or(0)
+}
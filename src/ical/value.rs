@@ -0,0 +1,255 @@
+use super::{Property, escape_text, unescape_text};
+
+/// The type of a property value, as named in jCal and xCal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    Binary,
+    Boolean,
+    CalAddress,
+    Date,
+    DateTime,
+    Duration,
+    Float,
+    Integer,
+    Period,
+    Recur,
+    Text,
+    Time,
+    Unknown,
+    Uri,
+    UtcOffset,
+}
+
+impl ValueType {
+    const ALL: [Self; 15] = [
+        Self::Binary,
+        Self::Boolean,
+        Self::CalAddress,
+        Self::Date,
+        Self::DateTime,
+        Self::Duration,
+        Self::Float,
+        Self::Integer,
+        Self::Period,
+        Self::Recur,
+        Self::Text,
+        Self::Time,
+        Self::Unknown,
+        Self::Uri,
+        Self::UtcOffset,
+    ];
+
+    /// The lowercase name of this type, e.g. `date-time`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Binary => "binary",
+            Self::Boolean => "boolean",
+            Self::CalAddress => "cal-address",
+            Self::Date => "date",
+            Self::DateTime => "date-time",
+            Self::Duration => "duration",
+            Self::Float => "float",
+            Self::Integer => "integer",
+            Self::Period => "period",
+            Self::Recur => "recur",
+            Self::Text => "text",
+            Self::Time => "time",
+            Self::Unknown => "unknown",
+            Self::Uri => "uri",
+            Self::UtcOffset => "utc-offset",
+        }
+    }
+
+    /// The type with the given name, or `Unknown`.
+    pub fn from_name(name: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|ty| ty.name().eq_ignore_ascii_case(name))
+            .unwrap_or(Self::Unknown)
+    }
+
+    /// The type of the values of a property when it has no VALUE parameter.
+    pub fn default_for(property_name: &str) -> Self {
+        match property_name.to_ascii_uppercase().as_str() {
+            "DTSTART" | "DTEND" | "DUE" | "RECURRENCE-ID" | "EXDATE" | "RDATE" | "DTSTAMP"
+            | "CREATED" | "LAST-MODIFIED" | "COMPLETED" => Self::DateTime,
+            "DURATION" | "TRIGGER" => Self::Duration,
+            "TZOFFSETFROM" | "TZOFFSETTO" => Self::UtcOffset,
+            "RRULE" | "EXRULE" => Self::Recur,
+            "ATTENDEE" | "ORGANIZER" => Self::CalAddress,
+            "URL" | "TZURL" | "ATTACH" => Self::Uri,
+            "PRIORITY" | "SEQUENCE" | "PERCENT-COMPLETE" | "REPEAT" => Self::Integer,
+            "GEO" => Self::Float,
+            "FREEBUSY" => Self::Period,
+            "SUMMARY" | "DESCRIPTION" | "LOCATION" | "COMMENT" | "CATEGORIES" | "RESOURCES"
+            | "CONTACT" | "RELATED-TO" | "UID" | "STATUS" | "TRANSP" | "CLASS" | "ACTION"
+            | "TZID" | "TZNAME" | "PRODID" | "VERSION" | "CALSCALE" | "METHOD"
+            | "REQUEST-STATUS" => Self::Text,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// The type of the values of a property.
+    pub fn of(property: &Property) -> Self {
+        match property.param("VALUE") {
+            Some(value) => Self::from_name(value),
+            None => Self::default_for(&property.name),
+        }
+    }
+}
+
+/// The separator of the values of a property, if it can hold several of them.
+fn separator(property_name: &str) -> Option<char> {
+    match property_name.to_ascii_uppercase().as_str() {
+        "CATEGORIES" | "RESOURCES" | "EXDATE" | "RDATE" | "FREEBUSY" => Some(','),
+        "GEO" => Some(';'),
+        _ => None,
+    }
+}
+
+/// Split the raw value of a property into its individual raw values.
+pub fn split_values(property: &Property) -> Vec<&str> {
+    let Some(separator) = separator(&property.name) else {
+        return vec![property.value.as_str()];
+    };
+
+    let mut values = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in property.value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == separator => {
+                values.push(&property.value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    values.push(&property.value[start..]);
+    values
+}
+
+/// Join individual raw values into the raw value of a property.
+pub fn join_values(property_name: &str, values: &[String]) -> String {
+    let separator = separator(property_name).unwrap_or(',');
+    values.join(&separator.to_string())
+}
+
+/// Convert a raw iCalendar value to its jCal and xCal form.
+///
+/// Dates, times and offsets gain their extended separators and text is unescaped. Recurrence
+/// rules are handled by [`recur_parts`].
+pub fn to_structured(ty: ValueType, raw: &str) -> String {
+    match ty {
+        ValueType::Text => unescape_text(raw),
+        ValueType::Date => extend_date(raw),
+        ValueType::DateTime => extend_date_time(raw),
+        ValueType::Time => extend_time(raw),
+        ValueType::UtcOffset => extend_time(raw),
+        ValueType::Period => match raw.split_once('/') {
+            Some((start, end)) if is_duration(end) => format!("{}/{end}", extend_date_time(start)),
+            Some((start, end)) => {
+                format!("{}/{}", extend_date_time(start), extend_date_time(end))
+            }
+            None => raw.to_owned(),
+        },
+        _ => raw.to_owned(),
+    }
+}
+
+/// Convert a jCal or xCal value to its raw iCalendar form, the inverse of [`to_structured`].
+pub fn from_structured(ty: ValueType, value: &str) -> String {
+    match ty {
+        ValueType::Text => escape_text(value),
+        ValueType::Date | ValueType::DateTime | ValueType::Period => value.replace(['-', ':'], ""),
+        ValueType::Time | ValueType::UtcOffset => value.replace(':', ""),
+        _ => value.to_owned(),
+    }
+}
+
+/// Split a raw RECUR value into its lowercase rule parts and their values, e.g.
+/// `("byday", ["MO", "WE"])`.
+pub fn recur_parts(raw: &str) -> Vec<(String, Vec<String>)> {
+    raw.split(';')
+        .filter_map(|part| part.split_once('='))
+        .map(|(name, values)| {
+            let name = name.to_ascii_lowercase();
+            let values = if name == "until" {
+                let ty = if values.len() == 8 {
+                    ValueType::Date
+                } else {
+                    ValueType::DateTime
+                };
+                vec![to_structured(ty, values)]
+            } else {
+                values.split(',').map(str::to_owned).collect()
+            };
+            (name, values)
+        })
+        .collect()
+}
+
+/// Join rule parts into a raw RECUR value, the inverse of [`recur_parts`].
+pub fn recur_from_parts(parts: &[(String, Vec<String>)]) -> String {
+    parts
+        .iter()
+        .map(|(name, values)| {
+            let values = if name.eq_ignore_ascii_case("until") {
+                values
+                    .iter()
+                    .map(|value| from_structured(ValueType::DateTime, value))
+                    .collect::<Vec<_>>()
+            } else {
+                values.clone()
+            };
+            format!("{}={}", name.to_ascii_uppercase(), values.join(","))
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn is_duration(value: &str) -> bool {
+    value.trim_start_matches(['+', '-']).starts_with('P')
+}
+
+/// `20250101` to `2025-01-01`.
+fn extend_date(raw: &str) -> String {
+    match (raw.get(0..4), raw.get(4..6), raw.get(6..8)) {
+        (Some(year), Some(month), Some(day)) if raw.len() == 8 => {
+            format!("{year}-{month}-{day}")
+        }
+        _ => raw.to_owned(),
+    }
+}
+
+/// `20250101T100000Z` to `2025-01-01T10:00:00Z`.
+fn extend_date_time(raw: &str) -> String {
+    match raw.split_once('T') {
+        Some((date, time)) => format!("{}T{}", extend_date(date), extend_time(time)),
+        None => extend_date(raw),
+    }
+}
+
+/// `100000Z` to `10:00:00Z`, and `+0100` to `+01:00`.
+fn extend_time(raw: &str) -> String {
+    let (sign, rest) = match raw.strip_prefix(['+', '-']) {
+        Some(rest) => (&raw[..1], rest),
+        None => ("", raw),
+    };
+    let (digits, suffix) = match rest.strip_suffix('Z') {
+        Some(digits) => (digits, "Z"),
+        None => (rest, ""),
+    };
+    if !digits.bytes().all(|b| b.is_ascii_digit()) || !matches!(digits.len(), 4 | 6) {
+        return raw.to_owned();
+    }
+
+    let parts = digits
+        .as_bytes()
+        .chunks(2)
+        .map(|chunk| std::str::from_utf8(chunk).expect("digits should be ASCII"))
+        .collect::<Vec<_>>();
+    format!("{sign}{}{suffix}", parts.join(":"))
+}
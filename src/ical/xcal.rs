@@ -0,0 +1,297 @@
+//! xCal (RFC 6321) conversion.
+
+use roxmltree::{Document, Node};
+
+use super::{
+    Component, Parameter, Property,
+    value::{self, ValueType},
+};
+
+const NAMESPACE: &str = "urn:ietf:params:xml:ns:icalendar-2.0";
+
+/// Convert a component to an xCal document.
+pub fn to_xcal(component: &Component) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str(&format!("<icalendar xmlns=\"{NAMESPACE}\">"));
+    write_component(&mut xml, component);
+    xml.push_str("</icalendar>\n");
+    xml
+}
+
+/// Convert the first component of an xCal document, the inverse of [`to_xcal`].
+pub fn from_xcal(xml: &str) -> Result<Component, String> {
+    let document = Document::parse(xml).map_err(|err| err.to_string())?;
+    let root = document.root_element();
+    if root.tag_name().name() != "icalendar" {
+        return Err(format!(
+            "unexpected root element {}",
+            root.tag_name().name()
+        ));
+    }
+    let component = elements(root)
+        .next()
+        .ok_or_else(|| "no component found".to_string())?;
+    component_from_xcal(component)
+}
+
+/// The type of the values of a parameter.
+fn param_value_type(param_name: &str) -> ValueType {
+    match param_name.to_ascii_uppercase().as_str() {
+        "ALTREP" | "DIR" => ValueType::Uri,
+        "DELEGATED-FROM" | "DELEGATED-TO" | "MEMBER" | "SENT-BY" => ValueType::CalAddress,
+        _ => ValueType::Text,
+    }
+}
+
+fn write_component(xml: &mut String, component: &Component) {
+    let name = component.name.to_ascii_lowercase();
+    xml.push_str(&format!("<{name}>"));
+    if !component.properties.is_empty() {
+        xml.push_str("<properties>");
+        for property in &component.properties {
+            write_property(xml, property);
+        }
+        xml.push_str("</properties>");
+    }
+    if !component.components.is_empty() {
+        xml.push_str("<components>");
+        for child in &component.components {
+            write_component(xml, child);
+        }
+        xml.push_str("</components>");
+    }
+    xml.push_str(&format!("</{name}>"));
+}
+
+fn write_property(xml: &mut String, property: &Property) {
+    let name = property.name.to_ascii_lowercase();
+    let ty = ValueType::of(property);
+    xml.push_str(&format!("<{name}>"));
+
+    let params = property
+        .params
+        .iter()
+        .filter(|param| !param.name.eq_ignore_ascii_case("VALUE"))
+        .collect::<Vec<_>>();
+    if !params.is_empty() {
+        xml.push_str("<parameters>");
+        for param in params {
+            let param_name = param.name.to_ascii_lowercase();
+            xml.push_str(&format!("<{param_name}>"));
+            for value in &param.values {
+                write_element(xml, param_value_type(&param.name).name(), value);
+            }
+            xml.push_str(&format!("</{param_name}>"));
+        }
+        xml.push_str("</parameters>");
+    }
+
+    let raw_values = value::split_values(property);
+    match (ty, raw_values.as_slice()) {
+        (ValueType::Float, [latitude, longitude]) if name == "geo" => {
+            write_element(xml, "latitude", latitude);
+            write_element(xml, "longitude", longitude);
+        }
+        _ => {
+            for raw in raw_values {
+                write_value(xml, ty, raw);
+            }
+        }
+    }
+
+    xml.push_str(&format!("</{name}>"));
+}
+
+fn write_value(xml: &mut String, ty: ValueType, raw: &str) {
+    match ty {
+        ValueType::Recur => {
+            xml.push_str("<recur>");
+            for (name, values) in value::recur_parts(raw) {
+                for value in values {
+                    write_element(xml, &name, &value);
+                }
+            }
+            xml.push_str("</recur>");
+        }
+        ValueType::Period => {
+            let period = value::to_structured(ty, raw);
+            let Some((start, end)) = period.split_once('/') else {
+                write_element(xml, ty.name(), &period);
+                return;
+            };
+            xml.push_str("<period>");
+            write_element(xml, "start", start);
+            if end.trim_start_matches(['+', '-']).starts_with('P') {
+                write_element(xml, "duration", end);
+            } else {
+                write_element(xml, "end", end);
+            }
+            xml.push_str("</period>");
+        }
+        ty => write_element(xml, ty.name(), &value::to_structured(ty, raw)),
+    }
+}
+
+fn write_element(xml: &mut String, name: &str, text: &str) {
+    xml.push_str(&format!("<{name}>{}</{name}>", escape(text)));
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn elements<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(Node::is_element)
+}
+
+fn text(node: Node<'_, '_>) -> String {
+    node.text().unwrap_or_default().to_owned()
+}
+
+fn component_from_xcal(node: Node<'_, '_>) -> Result<Component, String> {
+    let mut component = Component::new(node.tag_name().name());
+    for child in elements(node) {
+        match child.tag_name().name() {
+            "properties" => {
+                for property in elements(child) {
+                    component.properties.push(property_from_xcal(property)?);
+                }
+            }
+            "components" => {
+                for sub_component in elements(child) {
+                    component
+                        .components
+                        .push(component_from_xcal(sub_component)?);
+                }
+            }
+            other => return Err(format!("unexpected element {other}")),
+        }
+    }
+    Ok(component)
+}
+
+fn property_from_xcal(node: Node<'_, '_>) -> Result<Property, String> {
+    let name = node.tag_name().name();
+    let mut property = Property::new(name, "");
+    let mut ty = ValueType::Unknown;
+    let mut raw_values = Vec::new();
+
+    for child in elements(node) {
+        match child.tag_name().name() {
+            "parameters" => {
+                for param in elements(child) {
+                    property.params.push(Parameter {
+                        name: param.tag_name().name().to_ascii_uppercase(),
+                        values: elements(param).map(text).collect(),
+                    });
+                }
+            }
+            "latitude" | "longitude" => {
+                ty = ValueType::Float;
+                raw_values.push(text(child));
+            }
+            "recur" => {
+                ty = ValueType::Recur;
+                let mut parts: Vec<(String, Vec<String>)> = Vec::new();
+                for part in elements(child) {
+                    let part_name = part.tag_name().name();
+                    match parts.iter_mut().find(|(name, _)| name == part_name) {
+                        Some((_, values)) => values.push(text(part)),
+                        None => parts.push((part_name.to_owned(), vec![text(part)])),
+                    }
+                }
+                raw_values.push(value::recur_from_parts(&parts));
+            }
+            "period" => {
+                ty = ValueType::Period;
+                let mut start = String::new();
+                let mut end = String::new();
+                for part in elements(child) {
+                    match part.tag_name().name() {
+                        "start" => start = text(part),
+                        "end" | "duration" => end = text(part),
+                        other => return Err(format!("unexpected period element {other}")),
+                    }
+                }
+                raw_values.push(value::from_structured(ty, &format!("{start}/{end}")));
+            }
+            value_type => {
+                ty = ValueType::from_name(value_type);
+                raw_values.push(value::from_structured(ty, &text(child)));
+            }
+        }
+    }
+
+    if ty != ValueType::Unknown && ty != ValueType::default_for(name) {
+        property.set_param("VALUE", &ty.name().to_ascii_uppercase());
+    }
+    property.value = value::join_values(name, &raw_values);
+    Ok(property)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ICS: &str = "BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:1\r\n\
+        DTSTART;TZID=Europe/Paris:20250101T100000\r\n\
+        DURATION:PT1H\r\n\
+        SUMMARY:Lunch & <coffee>\r\n\
+        GEO:37.386013;-122.082932\r\n\
+        ATTENDEE;CN=Jane;DELEGATED-FROM=\"mailto:a@x\":mailto:jane@x\r\n\
+        RRULE:FREQ=WEEKLY;UNTIL=20250201T000000Z;BYDAY=MO,WE\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VFREEBUSY\r\n\
+        UID:2\r\n\
+        FREEBUSY:20250101T100000Z/PT1H,20250102T100000Z/20250102T110000Z\r\n\
+        END:VFREEBUSY\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn converts_properties() {
+        let xcal = to_xcal(&Component::parse(ICS).unwrap());
+        assert!(xcal.contains(
+            "<dtstart><parameters><tzid><text>Europe/Paris</text></tzid></parameters>\
+             <date-time>2025-01-01T10:00:00</date-time></dtstart>"
+        ));
+        assert!(xcal.contains("<summary><text>Lunch &amp; &lt;coffee&gt;</text></summary>"));
+        assert!(xcal.contains(
+            "<geo><latitude>37.386013</latitude><longitude>-122.082932</longitude></geo>"
+        ));
+        assert!(xcal.contains(
+            "<rrule><recur><freq>WEEKLY</freq><until>2025-02-01T00:00:00Z</until>\
+             <byday>MO</byday><byday>WE</byday></recur></rrule>"
+        ));
+        assert!(xcal.contains(
+            "<period><start>2025-01-01T10:00:00Z</start><duration>PT1H</duration></period>"
+        ));
+    }
+
+    #[test]
+    fn round_trips() {
+        let component = Component::parse(ICS).unwrap();
+        let xcal = to_xcal(&component);
+        assert_eq!(from_xcal(&xcal).unwrap(), component);
+        assert_eq!(from_xcal(&xcal).unwrap().to_string(), ICS);
+    }
+
+    #[test]
+    fn rejects_invalid_documents() {
+        assert!(from_xcal("<vcalendar/>").is_err());
+        assert!(from_xcal("<icalendar/>").is_err());
+        assert!(from_xcal("<icalendar><vevent><foo/></vevent></icalendar>").is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use std::{collections::BTreeMap, fmt};
+
+use gdk::{gio, glib, prelude::*};
+use tracing::warn;
+
+use crate::{
+    Event,
+    ical::{self, Component, Property},
+};
+
+pub(crate) const PRODID: &str = "-//ccm-eds//EN";
+
+/// How to import a component whose UID is already used in the target calendar.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UidConflictPolicy {
+    /// Keep the existing component and ignore the imported one.
+    #[default]
+    Skip,
+    /// Replace the existing component with the imported one.
+    Replace,
+    /// Import the component under a new UID, keeping both.
+    Duplicate,
+}
+
+/// The outcome of importing a single component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportStatus {
+    Created,
+    Replaced,
+    Skipped,
+    /// The component was removed from the calendar, by a cancellation.
+    Removed,
+    Failed(String),
+}
+
+/// A component of an imported iCalendar file, and what happened to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedComponent {
+    /// The UID of the component in the calendar, which differs from the imported one if it was
+    /// duplicated.
+    pub uid: String,
+    pub summary: String,
+    pub status: ImportStatus,
+}
+
+/// An error preventing an iCalendar file from being imported at all.
+#[derive(Debug)]
+pub enum ImportError {
+    /// The file could not be read.
+    Read(glib::Error),
+    /// The data is not valid iCalendar.
+    Parse(String),
+    /// The calendar is not backed by EDS.
+    UnknownCalendar(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(err) => write!(f, "failed to read iCalendar file: {err}"),
+            Self::Parse(err) => write!(f, "invalid iCalendar data: {err}"),
+            Self::UnknownCalendar(uri) => write!(f, "calendar {uri} cannot be written to"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// The content of an iCalendar file.
+pub(crate) struct IcsContent {
+    pub vtimezones: Vec<Component>,
+    /// The other top-level components, in file order.
+    pub components: Vec<Component>,
+}
+
+impl IcsContent {
+    /// Parse iCalendar data, either a VCALENDAR or a single component.
+    pub fn parse(data: &[u8]) -> Result<Self, ImportError> {
+        let data = String::from_utf8_lossy(data);
+        let root = Component::parse(&data).map_err(|err| ImportError::Parse(err.to_string()))?;
+        Ok(Self::from_root(root))
+    }
+
+    /// Parse jCal data, either a VCALENDAR or a single component.
+    pub fn parse_jcal(data: &str) -> Result<Self, ImportError> {
+        let jcal = serde_json::from_str(data).map_err(|err| ImportError::Parse(err.to_string()))?;
+        let root = ical::from_jcal(&jcal).map_err(ImportError::Parse)?;
+        Ok(Self::from_root(root))
+    }
+
+    /// Parse an xCal document.
+    pub fn parse_xcal(data: &str) -> Result<Self, ImportError> {
+        let root = ical::from_xcal(data).map_err(ImportError::Parse)?;
+        Ok(Self::from_root(root))
+    }
+
+    /// Split the components of a VCALENDAR, or take a single component.
+    pub fn from_root(root: Component) -> Self {
+        let components = if root.name == "VCALENDAR" {
+            root.components
+        } else {
+            vec![root]
+        };
+
+        let (vtimezones, components) = components
+            .into_iter()
+            .partition(|component| component.name == "VTIMEZONE");
+
+        Self {
+            vtimezones,
+            components,
+        }
+    }
+
+    /// Check that a component can be imported into a calendar.
+    pub fn check(&self, component: &Component) -> Result<(), String> {
+        if component.name != "VEVENT" {
+            return Err(format!("{} components are not supported", component.name));
+        }
+        if component.uid().is_none() {
+            return Err("component has no UID".to_string());
+        }
+        if let Some(tzid) = component
+            .tzids()
+            .find(|tzid| ical::resolve_tzid(tzid, &self.vtimezones).is_none())
+        {
+            return Err(format!("unknown time zone {tzid}"));
+        }
+        Ok(())
+    }
+
+    /// The VTIMEZONE definitions referenced by the given components.
+    pub fn referenced_vtimezones<'a>(
+        &'a self,
+        components: &'a [Component],
+    ) -> impl Iterator<Item = &'a Component> {
+        self.vtimezones.iter().filter(move |vtimezone| {
+            let Some(tzid) = vtimezone.property("TZID") else {
+                return false;
+            };
+            components
+                .iter()
+                .any(|component| component.tzids().any(|t| t == tzid.value))
+        })
+    }
+}
+
+/// Export events as a single iCalendar VCALENDAR, written to `stream`.
+///
+/// Each event is written as soon as it is serialized, followed by the VTIMEZONE definitions of
+/// the time zones used by the events.
+pub fn export_ics(
+    events: &[Event],
+    stream: &impl IsA<gio::OutputStream>,
+) -> Result<(), glib::Error> {
+    write(
+        stream,
+        &format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:{PRODID}\r\n"),
+    )?;
+    let mut tzids = BTreeMap::new();
+    for event in events {
+        let component = event.to_component();
+        add_tzids(&mut tzids, event, &component);
+        write(stream, &component.to_string())?;
+    }
+    for vtimezone in vtimezones(tzids) {
+        write(stream, &vtimezone.to_string())?;
+    }
+    write(stream, "END:VCALENDAR\r\n")
+}
+
+/// Export events as a single jCal VCALENDAR.
+///
+/// The VTIMEZONE definitions of the time zones used by the events are included.
+pub fn export_jcal(events: &[Event]) -> String {
+    ical::to_jcal(&export_vcalendar(events)).to_string()
+}
+
+/// Export events as a single xCal VCALENDAR.
+///
+/// The VTIMEZONE definitions of the time zones used by the events are included.
+pub fn export_xcal(events: &[Event]) -> String {
+    ical::to_xcal(&export_vcalendar(events))
+}
+
+/// The components of events, and the VTIMEZONE definitions of the time zones they use.
+fn export_components(events: &[Event]) -> (Vec<Component>, Vec<Component>) {
+    let mut tzids = BTreeMap::new();
+    let components = events
+        .iter()
+        .map(|event| {
+            let component = event.to_component();
+            add_tzids(&mut tzids, event, &component);
+            component
+        })
+        .collect();
+
+    (vtimezones(tzids), components)
+}
+
+/// Record the TZIDs used by the component of an event, along with the first event using each.
+fn add_tzids<'a>(tzids: &mut BTreeMap<String, &'a Event>, event: &'a Event, component: &Component) {
+    for tzid in component.tzids() {
+        tzids.entry(tzid.to_owned()).or_insert(event);
+    }
+}
+
+/// The VTIMEZONE definitions of TZIDs, looked up in the calendar of the event using them.
+fn vtimezones(tzids: BTreeMap<String, &Event>) -> Vec<Component> {
+    tzids
+        .into_iter()
+        .filter_map(|(tzid, event)| {
+            let vtimezone = event.manager().vtimezone(&event.calendar().uri(), &tzid);
+            if vtimezone.is_none() {
+                warn!("Exporting event {} without time zone {tzid}", event.uri());
+            }
+            vtimezone
+        })
+        .collect()
+}
+
+fn export_vcalendar(events: &[Event]) -> Component {
+    let (vtimezones, components) = export_components(events);
+
+    let mut vcalendar = Component::new("VCALENDAR");
+    vcalendar.set_property(Property::new("VERSION", "2.0"));
+    vcalendar.set_property(Property::text("PRODID", PRODID));
+    vcalendar.components = vtimezones;
+    vcalendar.components.extend(components);
+    vcalendar
+}
+
+fn write(stream: &impl IsA<gio::OutputStream>, data: &str) -> Result<(), glib::Error> {
+    stream
+        .write_all(data.as_bytes(), None::<&gio::Cancellable>)
+        .and_then(|(_, err)| err.map_or(Ok(()), Err))
+}
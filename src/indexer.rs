@@ -0,0 +1,337 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use gdk::{gio, glib, prelude::*};
+use tracing::{debug, warn};
+use tsparql::{SparqlConnection, prelude::*};
+
+use crate::{CCM_GRAPH, Calendar, Collection, CollectionsModel, Event, Provider, spawn};
+
+/// The IRI of a resource in the ccm graph.
+pub(crate) fn resource_iri(uri: &str) -> String {
+    format!("urn:ccm:{}", glib::Uri::escape_string(uri, None, false))
+}
+
+/// The URI of the resource with an IRI of the ccm graph, the inverse of [`resource_iri`].
+pub(crate) fn resource_uri(iri: &str) -> Option<String> {
+    let escaped = iri.strip_prefix("urn:ccm:")?;
+    glib::Uri::unescape_string(escaped, None::<&str>).map(|uri| uri.to_string())
+}
+
+/// A resource to write to the store.
+#[derive(Debug, Clone)]
+enum Change {
+    Provider(Provider),
+    Collection(Collection),
+    Calendar(Calendar),
+    Event(Event),
+    Delete,
+}
+
+/// Mirrors the providers, collections, calendars and events of a manager into the ccm graph of a
+/// Tracker store.
+///
+/// The models of the manager are watched, so that the store follows the changes learned from
+/// the backends. Changes are queued and written in a single batch once the main loop is idle.
+#[derive(Debug)]
+pub(crate) struct Indexer {
+    sparql_connection: SparqlConnection,
+    /// The changes to write with the URI of their resource, in the order they happened.
+    changes: RefCell<Vec<(String, Change)>>,
+    /// The position of the pending change of each resource.
+    change_positions: RefCell<HashMap<String, usize>>,
+    flush_scheduled: Cell<bool>,
+    /// The URIs of the indexed children of each indexed resource, to find the removed ones.
+    children: RefCell<HashMap<String, HashSet<String>>>,
+    providers: RefCell<HashSet<String>>,
+}
+
+impl Indexer {
+    pub fn new(sparql_connection: &SparqlConnection) -> Rc<Self> {
+        Rc::new(Self {
+            sparql_connection: sparql_connection.clone(),
+            changes: Default::default(),
+            change_positions: Default::default(),
+            flush_scheduled: Default::default(),
+            children: Default::default(),
+            providers: Default::default(),
+        })
+    }
+
+    /// Index the collections of a model and everything they hold, and keep them in sync.
+    pub fn watch_collections(self: &Rc<Self>, collections_model: &CollectionsModel) {
+        self.watch_children(
+            "",
+            collections_model.upcast_ref(),
+            |collection: &Collection| collection.uri(),
+            Self::watch_collection,
+        );
+    }
+
+    fn watch_collection(self: &Rc<Self>, collection: &Collection) {
+        let provider = collection.provider();
+        if self.providers.borrow_mut().insert(provider.uri()) {
+            self.queue(&provider.uri(), Change::Provider(provider.clone()));
+            let indexer = Rc::downgrade(self);
+            provider.connect_name_notify(move |provider| {
+                if let Some(indexer) = indexer.upgrade() {
+                    indexer.queue(&provider.uri(), Change::Provider(provider.clone()));
+                }
+            });
+        }
+
+        self.queue(&collection.uri(), Change::Collection(collection.clone()));
+        let indexer = Rc::downgrade(self);
+        collection.connect_name_notify(move |collection| {
+            if let Some(indexer) = indexer.upgrade()
+                && indexer.is_indexed("", &collection.uri())
+            {
+                indexer.queue(&collection.uri(), Change::Collection(collection.clone()));
+            }
+        });
+
+        self.watch_children(
+            &collection.uri(),
+            collection.calendars().upcast_ref(),
+            |calendar: &Calendar| calendar.uri(),
+            Self::watch_calendar,
+        );
+    }
+
+    fn watch_calendar(self: &Rc<Self>, calendar: &Calendar) {
+        self.queue(&calendar.uri(), Change::Calendar(calendar.clone()));
+        let indexer = Rc::downgrade(self);
+        let collection_uri = calendar.collection().uri();
+        calendar.connect_notify_local(None, move |calendar, _| {
+            if let Some(indexer) = indexer.upgrade()
+                && indexer.is_indexed(&collection_uri, &calendar.uri())
+            {
+                indexer.queue(&calendar.uri(), Change::Calendar(calendar.clone()));
+            }
+        });
+
+        self.watch_children(
+            &calendar.uri(),
+            calendar.events().upcast_ref(),
+            |event: &Event| event.uri(),
+            Self::watch_event,
+        );
+    }
+
+    fn watch_event(self: &Rc<Self>, event: &Event) {
+        self.queue(&event.uri(), Change::Event(event.clone()));
+        let indexer = Rc::downgrade(self);
+        let calendar_uri = event.calendar().uri();
+        event.connect_notify_local(None, move |event, _| {
+            if let Some(indexer) = indexer.upgrade()
+                && indexer.is_indexed(&calendar_uri, &event.uri())
+            {
+                indexer.queue(&event.uri(), Change::Event(event.clone()));
+            }
+        });
+    }
+
+    /// Index the items of a model, and keep them in sync as it changes.
+    fn watch_children<T: IsA<glib::Object>>(
+        self: &Rc<Self>,
+        parent_uri: &str,
+        model: &gio::ListModel,
+        uri: fn(&T) -> String,
+        watch: fn(&Rc<Self>, &T),
+    ) {
+        self.sync_children(parent_uri, model, uri, watch);
+
+        let indexer = Rc::downgrade(self);
+        let parent_uri = parent_uri.to_string();
+        model.connect_items_changed(move |model, _, _, _| {
+            if let Some(indexer) = indexer.upgrade() {
+                indexer.sync_children(&parent_uri, model, uri, watch);
+            }
+        });
+    }
+
+    /// Index the new items of a model, and remove the ones that are no longer in it.
+    fn sync_children<T: IsA<glib::Object>>(
+        self: &Rc<Self>,
+        parent_uri: &str,
+        model: &gio::ListModel,
+        uri: fn(&T) -> String,
+        watch: fn(&Rc<Self>, &T),
+    ) {
+        let items = model.iter::<T>().filter_map(Result::ok).collect::<Vec<_>>();
+        let current = items.iter().map(uri).collect::<HashSet<_>>();
+        let previous = self
+            .children
+            .borrow_mut()
+            .insert(parent_uri.to_string(), current.clone())
+            .unwrap_or_default();
+
+        for removed in previous.difference(&current) {
+            self.remove(removed);
+        }
+        for item in items {
+            if !previous.contains(&uri(&item)) {
+                watch(self, &item);
+            }
+        }
+    }
+
+    /// Remove a resource and all its children from the store.
+    fn remove(self: &Rc<Self>, uri: &str) {
+        let children = self.children.borrow_mut().remove(uri);
+        for child in children.into_iter().flatten() {
+            self.remove(&child);
+        }
+        self.queue(uri, Change::Delete);
+    }
+
+    /// Whether a resource is indexed as a child of the given parent.
+    fn is_indexed(&self, parent_uri: &str, uri: &str) -> bool {
+        self.children
+            .borrow()
+            .get(parent_uri)
+            .is_some_and(|children| children.contains(uri))
+    }
+
+    /// Queue a change, replacing the pending change of the same resource.
+    fn queue(self: &Rc<Self>, uri: &str, change: Change) {
+        {
+            let mut changes = self.changes.borrow_mut();
+            let mut change_positions = self.change_positions.borrow_mut();
+            match change_positions.get(uri) {
+                Some(position) => changes[*position].1 = change,
+                None => {
+                    change_positions.insert(uri.to_string(), changes.len());
+                    changes.push((uri.to_string(), change));
+                }
+            }
+        }
+
+        if !self.flush_scheduled.replace(true) {
+            self.schedule_flush();
+        }
+    }
+
+    /// Flush the queued changes once the main loop is idle.
+    fn schedule_flush(self: &Rc<Self>) {
+        let indexer = Rc::downgrade(self);
+        spawn!(glib::Priority::DEFAULT_IDLE, async move {
+            if let Some(indexer) = indexer.upgrade() {
+                indexer.flush().await;
+            }
+        });
+    }
+
+    /// Write the queued changes to the store.
+    ///
+    /// The changes queued while they are written are flushed afterwards, so that batches never
+    /// overlap.
+    async fn flush(self: Rc<Self>) {
+        let changes = self.changes.take();
+        self.change_positions.take();
+        if !changes.is_empty() {
+            self.write(&changes).await;
+        }
+
+        if self.changes.borrow().is_empty() {
+            self.flush_scheduled.set(false);
+        } else {
+            self.schedule_flush();
+        }
+    }
+
+    /// Write changes to the store in a single batch.
+    async fn write(&self, changes: &[(String, Change)]) {
+        let batch = self.sparql_connection.create_batch();
+        for (uri, change) in changes {
+            let iri = resource_iri(uri);
+            let resource = match change {
+                Change::Provider(provider) => provider_resource(&iri, provider),
+                Change::Collection(collection) => collection_resource(&iri, collection),
+                Change::Calendar(calendar) => calendar_resource(&iri, calendar),
+                Change::Event(event) => event_resource(&iri, event),
+                Change::Delete => {
+                    batch.add_sparql(&format!(
+                        "DELETE WHERE {{ GRAPH <{CCM_GRAPH}> {{ <{iri}> ?p ?o }} }}"
+                    ));
+                    continue;
+                }
+            };
+            batch.add_resource(Some(CCM_GRAPH), &resource);
+        }
+
+        match batch.execute_future().await {
+            Ok(()) => debug!("Indexed {} changes", changes.len()),
+            Err(err) => warn!("Failed to index {} changes: {err}", changes.len()),
+        }
+    }
+}
+
+fn provider_resource(iri: &str, provider: &Provider) -> tsparql::Resource {
+    let resource = tsparql::Resource::new(Some(iri));
+    resource.set_uri("rdf:type", "ccm:Provider");
+    resource.set_string("ccm:providerName", &provider.name());
+    resource
+}
+
+fn collection_resource(iri: &str, collection: &Collection) -> tsparql::Resource {
+    let resource = tsparql::Resource::new(Some(iri));
+    resource.set_uri("rdf:type", "ccm:Collection");
+    resource.set_uri("ccm:provider", &resource_iri(&collection.provider().uri()));
+    resource.set_string("ccm:collectionName", &collection.name());
+    resource
+}
+
+fn calendar_resource(iri: &str, calendar: &Calendar) -> tsparql::Resource {
+    let resource = tsparql::Resource::new(Some(iri));
+    resource.set_uri("rdf:type", "ccm:Calendar");
+    resource.set_uri(
+        "ccm:collection",
+        &resource_iri(&calendar.collection().uri()),
+    );
+    resource.set_string("ccm:calendarName", &calendar.name());
+    if let Some(color) = calendar.color() {
+        resource.set_string("ccm:color", &color.to_string());
+    }
+    resource
+}
+
+fn event_resource(iri: &str, event: &Event) -> tsparql::Resource {
+    let resource = tsparql::Resource::new(Some(iri));
+    resource.set_uri("rdf:type", "ccm:Event");
+    resource.set_uri("ccm:calendar", &resource_iri(&event.calendar().uri()));
+    resource.set_string("ccm:eventName", &event.name());
+    resource.set_string("ccm:eventDescription", &event.description());
+    resource.set_string("ccm:eventLocation", &event.location());
+    if let Some(timeframe) = event.timeframe() {
+        resource.set_boolean("ccm:eventAllDay", timeframe.all_day());
+        for (property, zoned) in [
+            ("ccm:eventStart", timeframe.start()),
+            ("ccm:eventEnd", timeframe.end()),
+        ] {
+            // Dates are floating, so they are stored as their midnight in UTC rather than as the
+            // instant they start at here
+            let date_time = if timeframe.all_day() {
+                let date = zoned.0.date();
+                glib::DateTime::from_utc(
+                    date.year().into(),
+                    date.month().into(),
+                    date.day().into(),
+                    0,
+                    0,
+                    0.,
+                )
+            } else {
+                glib::DateTime::from_unix_utc(zoned.0.timestamp().as_second())
+            };
+            match date_time {
+                Ok(date_time) => resource.set_datetime(property, &date_time),
+                Err(err) => warn!("Invalid date of event {}: {err}", event.uri()),
+            }
+        }
+    }
+    resource
+}
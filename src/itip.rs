@@ -0,0 +1,135 @@
+use std::fmt;
+
+use jiff::tz::TimeZone;
+
+use crate::{
+    ical::{self, Component, Property},
+    ics::{IcsContent, PRODID},
+};
+
+/// The method of an iTIP (RFC 5546) scheduling message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItipMethod {
+    /// An invitation, or an update of an existing meeting.
+    Request,
+    /// The cancellation of a meeting, or of some of its instances.
+    Cancel,
+    /// The response of an attendee to an invitation.
+    Reply,
+}
+
+impl ItipMethod {
+    fn from_ical(value: &str) -> Option<Self> {
+        match value.to_ascii_uppercase().as_str() {
+            "REQUEST" => Some(Self::Request),
+            "CANCEL" => Some(Self::Cancel),
+            "REPLY" => Some(Self::Reply),
+            _ => None,
+        }
+    }
+}
+
+/// An error preventing a scheduling message from being sent or processed.
+#[derive(Debug)]
+pub enum ItipError {
+    /// The data is not a valid iCalendar scheduling message.
+    Parse(String),
+    /// The message has no METHOD, or one that is not supported.
+    UnsupportedMethod(String),
+    /// The calendar is not backed by EDS.
+    UnknownCalendar(String),
+    /// None of our addresses is an attendee of the event.
+    NotInvited,
+    /// The event has no organizer to reply to.
+    NoOrganizer,
+    /// The backend failed to store or send the message.
+    Backend(String),
+}
+
+impl fmt::Display for ItipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(err) => write!(f, "invalid scheduling message: {err}"),
+            Self::UnsupportedMethod(method) => {
+                write!(f, "unsupported scheduling method {method:?}")
+            }
+            Self::UnknownCalendar(uri) => write!(f, "calendar {uri} cannot be written to"),
+            Self::NotInvited => write!(f, "not an attendee of the event"),
+            Self::NoOrganizer => write!(f, "the event has no organizer"),
+            Self::Backend(err) => write!(f, "failed to process scheduling message: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ItipError {}
+
+/// A parsed iTIP scheduling message.
+pub(crate) struct ItipMessage {
+    pub method: ItipMethod,
+    pub content: IcsContent,
+}
+
+impl ItipMessage {
+    /// Parse an iCalendar VCALENDAR holding a scheduling message.
+    pub fn parse(data: &[u8]) -> Result<Self, ItipError> {
+        let data = String::from_utf8_lossy(data);
+        let root = Component::parse(&data).map_err(|err| ItipError::Parse(err.to_string()))?;
+        if root.name != "VCALENDAR" {
+            return Err(ItipError::Parse(format!(
+                "expected VCALENDAR, found {}",
+                root.name
+            )));
+        }
+
+        let method = root.text("METHOD").unwrap_or_default();
+        let method = ItipMethod::from_ical(&method).ok_or(ItipError::UnsupportedMethod(method))?;
+        let mut content = IcsContent::from_root(root);
+        content
+            .components
+            .retain(|component| component.name == "VEVENT");
+        Ok(Self { method, content })
+    }
+}
+
+/// Build the REPLY of an attendee to an event, as an iCalendar VCALENDAR.
+///
+/// The reply holds the identifying properties of the event, its organizer, and only the given
+/// attendee.
+pub(crate) fn reply(event: &Component, attendee: Property) -> Component {
+    let mut vevent = Component::new("VEVENT");
+    for name in [
+        "UID",
+        "RECURRENCE-ID",
+        "SEQUENCE",
+        "DTSTART",
+        "DTEND",
+        "DURATION",
+        "SUMMARY",
+        "ORGANIZER",
+    ] {
+        if let Some(property) = event.property(name) {
+            vevent.set_property(property.clone());
+        }
+    }
+    vevent.set_property(ical::date_time_property(
+        "DTSTAMP",
+        &jiff::Zoned::now().with_time_zone(TimeZone::UTC),
+        false,
+    ));
+    vevent.properties.push(attendee);
+
+    let mut vcalendar = Component::new("VCALENDAR");
+    vcalendar.set_property(Property::new("VERSION", "2.0"));
+    vcalendar.set_property(Property::text("PRODID", PRODID));
+    vcalendar.set_property(Property::new("METHOD", "REPLY"));
+    vcalendar.components.push(vevent);
+    vcalendar
+}
+
+/// The SEQUENCE of a component, or 0.
+pub(crate) fn sequence(component: &Component) -> i64 {
+    component
+        .property("SEQUENCE")
+        .and_then(|property| property.value.trim().parse().ok())
+        .unwrap_or(0)
+}
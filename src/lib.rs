@@ -1,21 +1,50 @@
+mod attachment;
+mod attendee;
 mod calendar;
 mod collection;
 mod collections_model;
+mod csv;
 mod event;
+mod free_busy;
+mod ical;
+mod ics;
+mod indexer;
+mod itip;
 mod manager;
+mod memo;
+mod memo_list;
 mod pre_resource;
 mod provider;
+mod query;
 mod resource;
+mod scheduling;
+mod search;
+mod store;
+mod task;
+mod task_list;
 mod timeframe;
 mod utils;
 
+pub use attachment::*;
+pub use attendee::*;
 pub use calendar::*;
 pub use collection::*;
 pub use collections_model::*;
+pub use csv::*;
 pub use event::*;
+pub use ics::*;
+pub use itip::*;
 pub use manager::*;
+pub use memo::*;
+pub use memo_list::*;
 pub use provider::*;
+pub use query::*;
 pub use resource::*;
+pub use scheduling::*;
+pub use search::*;
+pub use store::*;
+pub use task::*;
+pub use task_list::*;
 pub use timeframe::*;
 
 #[doc(no_inline)]
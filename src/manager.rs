@@ -1,12 +1,16 @@
 use std::{
-    cell::{OnceCell, RefCell},
-    collections::HashMap,
-    sync::{Mutex, MutexGuard},
+    cell::{Cell, OnceCell, RefCell},
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicBool, Ordering},
+    },
 };
 
 use gdk::{
     RGBA,
-    gio::{self, BusType, DBusCallFlags, DBusProxy, DBusProxyFlags, ListStore},
+    gio::{self, ListStore},
     glib::{self, Object, clone},
     prelude::*,
     subclass::prelude::*,
@@ -15,18 +19,68 @@ use tracing::{debug, info, warn};
 use tsparql::{Notifier, NotifierEvent, NotifierEventType, SparqlConnection, prelude::*};
 
 use crate::{
-    Calendar, Collection, CollectionsModel, Event, Provider, Resource, Timeframe, Zoned,
-    pre_resource::PreResource, spawn, utils::*,
+    Attendee, Calendar, Collection, CollectionsModel, Event, EventMatch, ImportError, ImportStatus,
+    ImportedComponent, ItipError, ItipMethod, Memo, MemoList, ParticipationStatus, Provider, Query,
+    QueryField, Resource, SlotSearch, SubtaskPolicy, Task, TaskList, Timeframe, UidConflictPolicy,
+    Zoned,
+    attendee::email_from_address,
+    event::event_uri,
+    free_busy::{Interval, event_intervals, merge_intervals, vfreebusy_intervals},
+    ical::{self, Component},
+    ics::IcsContent,
+    indexer::{Indexer, resource_uri},
+    itip::{self, ItipMessage},
+    pre_resource::{PreResource, StatementCache},
+    search::{SNIPPET_MATCH_END, SNIPPET_MATCH_START, fts_query},
+    spawn,
+    store::CCM_GRAPH,
+    task::is_parent_relation,
+    utils::*,
 };
 
+const EDS_PROVIDER_URI: &str = "eds";
+/// The number of resources of a Tracker store to load at once.
+const STORE_LOAD_CHUNK_SIZE: usize = 256;
+/// The EDS calendar backends storing their data on this computer.
+const LOCAL_BACKENDS: &[&str] = &["local"];
+/// The EDS calendar backends generated from other data, which never keep anyone busy.
+const NO_FREE_BUSY_BACKENDS: &[&str] = &["contacts", "weather"];
+
 mod imp {
     use super::*;
 
     #[derive(Debug, Default, glib::Properties)]
     #[properties(wrapper_type = super::Manager)]
     pub struct Manager {
+        /// Whether the resources are loaded from EDS, or only from a Tracker store.
+        #[property(get, construct_only, default = true)]
+        use_eds: Cell<bool>,
         connection: OnceCell<zbus::blocking::Connection>,
         resource_pool: OnceCell<Mutex<HashMap<String, Resource>>>,
+        /// The sources of the EDS registry, by UID.
+        sources: RefCell<HashMap<String, SourceInfo>>,
+        eds_calendars: RefCell<HashMap<String, EdsCalendar>>,
+        /// The views notifying the changes of the EDS calendars, by UID of their source.
+        eds_views: RefCell<HashMap<String, EdsCalendarView>>,
+        /// Whether the sources are going to be read again after a change of the registry.
+        sources_sync_scheduled: Cell<bool>,
+        /// The calendars whose data is all stored on this computer, whose free/busy information
+        /// is computed from their events.
+        local_calendars: RefCell<HashSet<String>>,
+        notifier: OnceCell<Notifier>,
+        /// The prepared statements of the Tracker store the resources are loaded from or indexed
+        /// into.
+        statements: OnceCell<StatementCache>,
+        /// The changes of the Tracker store not handled yet, in the order they happened.
+        store_changes: RefCell<Vec<(NotifierEventType, String)>>,
+        /// Whether the Tracker store is being loaded or its changes handled, so that they are
+        /// handled one after the other.
+        handling_store_changes: Cell<bool>,
+        /// The resources of the Tracker store waiting for their parent to be created, by the
+        /// URI of their parent.
+        pending_resources: RefCell<HashMap<String, Vec<PreResource>>>,
+        /// The mirror of the resources in a Tracker store, if any.
+        indexer: OnceCell<Rc<Indexer>>,
         #[property(get)]
         collections_model: OnceCell<CollectionsModel>,
     }
@@ -43,6 +97,14 @@ mod imp {
         fn constructed(&self) {
             self.parent_constructed();
 
+            self.resource_pool.get_or_init(Default::default);
+            self.collections_model
+                .get_or_init(CollectionsModel::default);
+
+            if !self.use_eds.get() {
+                return;
+            }
+
             let Ok(_) = self
                 .connection
                 .set(zbus::blocking::Connection::session().unwrap())
@@ -50,9 +112,6 @@ mod imp {
                 panic!("Failed to set session connection");
             };
 
-            self.collections_model
-                .get_or_init(CollectionsModel::default);
-
             spawn!(clone!(
                 #[weak(rename_to = imp)]
                 self,
@@ -72,40 +131,1080 @@ mod imp {
                 .unwrap()
         }
 
-        fn retrieve_resources(&self) {
-            let connection = self
-                .connection
+        fn connection(&self) -> &zbus::blocking::Connection {
+            self.connection
                 .get()
-                .expect("Connection should be initialized");
+                .expect("Connection should be initialized")
+        }
 
-            let proxy = zbus::blocking::fdo::ObjectManagerProxy::builder(connection)
-                .destination("org.gnome.evolution.dataserver.Sources5")
-                .unwrap()
-                .path("/org/gnome/evolution/dataserver/SourceManager")
-                .unwrap()
-                .build()
-                .unwrap();
-
-            let mut sources = HashMap::new();
-
-            // Get all managed objects
-            let objects = proxy.get_managed_objects().unwrap();
-            for (object_path, _) in objects {
-                let proxy = zbus::blocking::Proxy::new(
-                    connection,
-                    "org.gnome.evolution.dataserver.Sources5",
-                    object_path.clone(),
-                    "org.gnome.evolution.dataserver.Source",
+        fn retrieve_resources(&self) {
+            let obj = self.obj();
+            let provider = Provider::new(&obj, EDS_PROVIDER_URI, "Evolution Data Server");
+            self.resource_pool().insert(
+                EDS_PROVIDER_URI.to_string(),
+                Resource::Provider(provider.clone()),
+            );
+
+            let sources_rule = zbus::MatchRule::builder()
+                .msg_type(zbus::message::Type::Signal)
+                .sender(SOURCES_BUS_NAME)
+                .and_then(|builder| builder.path_namespace(SOURCE_MANAGER_PATH))
+                .map(|builder| builder.build());
+            match sources_rule {
+                Ok(rule) => self.watch_signals(rule, |imp, _| imp.queue_sources_sync()),
+                Err(err) => warn!("Failed to watch EDS sources: {err}"),
+            }
+            match calendar_views_rule() {
+                Ok(rule) => self.watch_signals(rule, Self::handle_view_signal),
+                Err(err) => warn!("Failed to watch EDS calendar views: {err}"),
+            }
+
+            self.sync_sources();
+        }
+
+        /// Call a handler on the main thread for each D-Bus signal matching a rule, as long as
+        /// the manager is alive.
+        ///
+        /// The blocking connection can only wait for signals on a thread of its own.
+        fn watch_signals(
+            &self,
+            rule: zbus::MatchRule<'static>,
+            handler: impl Fn(&Self, &zbus::Message) + Send + Sync + 'static,
+        ) {
+            let messages = match zbus::blocking::MessageIterator::for_match_rule(
+                rule,
+                self.connection(),
+                None,
+            ) {
+                Ok(messages) => messages,
+                Err(err) => {
+                    warn!("Failed to watch EDS signals: {err}");
+                    return;
+                }
+            };
+
+            let obj = glib::SendWeakRef::from(self.obj().downgrade());
+            let handler = Arc::new(handler);
+            let alive = Arc::new(AtomicBool::new(true));
+            std::thread::spawn(move || {
+                for message in messages {
+                    if !alive.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let message = match message {
+                        Ok(message) => message,
+                        Err(err) => {
+                            debug!("Failed to receive EDS signal: {err}");
+                            continue;
+                        }
+                    };
+
+                    let (obj, handler, alive) = (obj.clone(), handler.clone(), alive.clone());
+                    glib::MainContext::default().invoke(move || match obj.upgrade() {
+                        Some(obj) => handler(obj.imp(), &message),
+                        None => alive.store(false, Ordering::Relaxed),
+                    });
+                }
+            });
+        }
+
+        /// Read the sources again once idle, coalescing the signals of a change of the registry.
+        fn queue_sources_sync(&self) {
+            if self.sources_sync_scheduled.replace(true) {
+                return;
+            }
+            spawn!(
+                glib::Priority::DEFAULT_IDLE,
+                clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    async move {
+                        imp.sources_sync_scheduled.set(false);
+                        imp.sync_sources();
+                    }
                 )
-                .unwrap();
-                let data = proxy.get_property::<String>("Data").unwrap();
-                let uid = proxy.get_property::<String>("UID").unwrap();
+            );
+        }
+
+        /// Apply the components added, modified or removed in the view of a calendar, task list
+        /// or memo list.
+        fn handle_view_signal(&self, message: &zbus::Message) {
+            let header = message.header();
+            let (Some(path), Some(member)) = (header.path(), header.member()) else {
+                return;
+            };
+            let Some(uid) = self
+                .eds_views
+                .borrow()
+                .iter()
+                .find(|(_, view)| view.path().as_str() == path.as_str())
+                .map(|(uid, _)| uid.clone())
+            else {
+                return;
+            };
 
-                if let Some(source_info) = parse_source_data(object_path.clone(), uid, data) {
-                    sources.insert(object_path, (source_info, proxy));
+            let change = match ViewChange::from_message(message) {
+                Ok(Some(change)) => change,
+                Ok(None) => return,
+                Err(err) => {
+                    warn!("Invalid {member} signal of {uid}: {err}");
+                    return;
+                }
+            };
+            match change {
+                ViewChange::Changed(objects) => {
+                    let resource = self.resource_pool().get(&uid).cloned();
+                    match resource {
+                        Some(Resource::Calendar(calendar)) => {
+                            self.apply_events(&calendar, &objects, &[]);
+                        }
+                        Some(Resource::TaskList(task_list)) => {
+                            self.apply_tasks(&task_list, &objects);
+                        }
+                        Some(Resource::MemoList(memo_list)) => {
+                            self.apply_memos(&memo_list, &objects);
+                        }
+                        _ => {}
+                    }
+                }
+                ViewChange::Removed(ids) => {
+                    for (component_uid, recurrence_id) in ids {
+                        self.remove_resource(&event_uri(
+                            &uid,
+                            &component_uid,
+                            recurrence_id.as_deref(),
+                        ));
+                    }
                 }
             }
         }
+
+        /// Read the sources of the registry, and create, update or remove the matching
+        /// collections, calendars, task lists and memo lists.
+        pub(super) fn sync_sources(&self) {
+            let connection = self.connection();
+            let proxy = zbus::blocking::fdo::ObjectManagerProxy::builder(connection)
+                .destination(SOURCES_BUS_NAME)
+                .and_then(|builder| builder.path(SOURCE_MANAGER_PATH))
+                .and_then(|builder| builder.build());
+            let objects = match proxy.and_then(|proxy| Ok(proxy.get_managed_objects()?)) {
+                Ok(objects) => objects,
+                Err(err) => {
+                    warn!("Failed to read EDS sources: {err}");
+                    return;
+                }
+            };
+
+            let sources = objects
+                .into_iter()
+                .filter_map(|(object_path, interfaces)| {
+                    read_source(
+                        connection,
+                        object_path,
+                        interfaces.keys().map(|interface| interface.as_str()),
+                    )
+                })
+                .map(|source_info| (source_info.uid.clone(), source_info))
+                .collect::<HashMap<_, _>>();
+            let previous = self.sources.replace(sources.clone());
+
+            for uid in previous.keys().filter(|uid| !sources.contains_key(*uid)) {
+                self.remove_resource(uid);
+            }
+            for source_info in sources.values() {
+                match previous.get(&source_info.uid) {
+                    None => self.add_source(source_info),
+                    Some(previous) if previous != source_info => self.update_source(source_info),
+                    Some(_) => {}
+                }
+            }
+        }
+
+        /// The source with the given UID.
+        pub(super) fn source(&self, uid: &str) -> Option<SourceInfo> {
+            self.sources.borrow().get(uid).cloned()
+        }
+
+        /// Create the calendar, task list or memo list of a source, and load its data.
+        fn add_source(&self, source_info: &SourceInfo) {
+            if source_info.kind.group().is_none() {
+                return;
+            }
+            let Some(collection) = self.source_collection(source_info) else {
+                warn!("Source {} has no collection", source_info.uid);
+                return;
+            };
+
+            let obj = self.obj();
+            let connection = self.connection();
+            let color = source_color(source_info);
+            match source_info.kind {
+                SourceKind::MemoList => {
+                    let memo_list = MemoList::new(
+                        &obj,
+                        &collection,
+                        &source_info.uid,
+                        &source_info.display_name,
+                        color,
+                    );
+                    collection.add_memo_list(&memo_list);
+                    self.resource_pool().insert(
+                        source_info.uid.clone(),
+                        Resource::MemoList(memo_list.clone()),
+                    );
+
+                    match EdsCalendar::open_memo_list(connection, &source_info.uid) {
+                        Ok(eds_memo_list) => {
+                            self.watch_eds_calendar(&source_info.uid, &eds_memo_list);
+                            self.refresh_memos(&memo_list, &eds_memo_list, &Query::All);
+                            self.eds_calendars
+                                .borrow_mut()
+                                .insert(source_info.uid.clone(), eds_memo_list);
+                        }
+                        Err(err) => warn!("Failed to open memo list {}: {err}", source_info.uid),
+                    }
+                }
+                SourceKind::TaskList => {
+                    let task_list = TaskList::new(
+                        &obj,
+                        &collection,
+                        &source_info.uid,
+                        &source_info.display_name,
+                        color,
+                    );
+                    collection.add_task_list(&task_list);
+                    self.resource_pool().insert(
+                        source_info.uid.clone(),
+                        Resource::TaskList(task_list.clone()),
+                    );
+
+                    match EdsCalendar::open_task_list(connection, &source_info.uid) {
+                        Ok(eds_task_list) => {
+                            self.watch_eds_calendar(&source_info.uid, &eds_task_list);
+                            self.refresh_tasks(&task_list, &eds_task_list, &Query::All);
+                            self.eds_calendars
+                                .borrow_mut()
+                                .insert(source_info.uid.clone(), eds_task_list);
+                        }
+                        Err(err) => warn!("Failed to open task list {}: {err}", source_info.uid),
+                    }
+                }
+                _ => {
+                    let calendar = Calendar::new(
+                        &obj,
+                        &collection,
+                        &source_info.uid,
+                        &source_info.display_name,
+                        color,
+                    );
+                    collection.add_calendar(&calendar);
+                    self.resource_pool().insert(
+                        source_info.uid.clone(),
+                        Resource::Calendar(calendar.clone()),
+                    );
+
+                    match EdsCalendar::open(connection, &source_info.uid) {
+                        Ok(eds_calendar) => {
+                            self.watch_eds_calendar(&source_info.uid, &eds_calendar);
+                            self.load_events(&calendar, &eds_calendar);
+                            if LOCAL_BACKENDS.contains(&source_info.backend_name.as_str()) {
+                                self.local_calendars
+                                    .borrow_mut()
+                                    .insert(source_info.uid.clone());
+                            }
+                            self.eds_calendars
+                                .borrow_mut()
+                                .insert(source_info.uid.clone(), eds_calendar);
+                        }
+                        Err(err) => warn!("Failed to open calendar {}: {err}", source_info.uid),
+                    }
+                }
+            }
+        }
+
+        /// Follow the changes of an EDS calendar, task list or memo list, before loading it so
+        /// that none is missed.
+        fn watch_eds_calendar(&self, uid: &str, eds_calendar: &EdsCalendar) {
+            match eds_calendar.create_view(&Query::All.to_string()) {
+                Ok(view) => {
+                    self.eds_views.borrow_mut().insert(uid.to_string(), view);
+                }
+                Err(err) => warn!("Failed to watch changes of {uid}: {err}"),
+            }
+        }
+
+        /// The collection of a source, created along with its resource if needed.
+        fn source_collection(&self, source_info: &SourceInfo) -> Option<Collection> {
+            let sources = self.sources.borrow();
+            let parent = source_info
+                .parent
+                .as_deref()
+                .and_then(|parent| sources.get(parent))
+                .filter(|parent| parent.kind == SourceKind::Collection)?;
+
+            let existing = self.resource_pool().get(&parent.uid).cloned();
+            if let Some(Resource::Collection(collection)) = existing {
+                return Some(collection);
+            }
+            let Some(Resource::Provider(provider)) =
+                self.resource_pool().get(EDS_PROVIDER_URI).cloned()
+            else {
+                return None;
+            };
+
+            let collection =
+                Collection::new(&self.obj(), &provider, &parent.uid, &parent.display_name);
+            let (address, name) = identity(parent, &sources);
+            collection.set_identity_address(address.as_deref());
+            collection.set_identity_name(name.as_deref());
+            provider.add_collection(&collection);
+            self.resource_pool()
+                .insert(parent.uid.clone(), Resource::Collection(collection.clone()));
+            self.collections_model().append(&collection);
+            Some(collection)
+        }
+
+        /// Update the resource of a source whose name or color changed.
+        fn update_source(&self, source_info: &SourceInfo) {
+            let Some(resource) = self.resource_pool().get(&source_info.uid).cloned() else {
+                // Sources that were disabled or lacked their collection may now be complete
+                self.add_source(source_info);
+                return;
+            };
+            let color = source_color(source_info);
+            match resource {
+                Resource::Collection(collection) => {
+                    collection.set_name(source_info.display_name.clone());
+                }
+                Resource::Calendar(calendar) => {
+                    calendar.emit_updated(&source_info.display_name, color);
+                }
+                Resource::TaskList(task_list) => {
+                    task_list.set_name(source_info.display_name.clone());
+                    task_list.set_color(Some(color));
+                }
+                Resource::MemoList(memo_list) => {
+                    memo_list.set_name(source_info.display_name.clone());
+                    memo_list.set_color(Some(color));
+                }
+                _ => {}
+            }
+        }
+
+        /// Load the resources of a Tracker store, and keep them in sync with its changes.
+        pub(super) fn watch_store(&self, sparql_connection: &SparqlConnection) {
+            let Some(notifier) = sparql_connection.create_notifier() else {
+                warn!("Failed to create notifier for Tracker store");
+                return;
+            };
+            notifier.connect_events(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _, graph, events| {
+                    if graph == CCM_GRAPH {
+                        imp.queue_notifier_events(&events);
+                    }
+                }
+            ));
+
+            self.notifier
+                .set(notifier)
+                .expect("Notifier should only be set once");
+            self.statements
+                .set(StatementCache::new(sparql_connection))
+                .expect("Statements should only be set once");
+
+            // The changes made while loading are handled once it is done
+            self.handling_store_changes.set(true);
+            spawn!(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                async move {
+                    imp.load_store().await;
+                    imp.handle_store_changes().await;
+                }
+            ));
+        }
+
+        /// Create the resources of the Tracker store.
+        async fn load_store(&self) {
+            let Some(statements) = self.statements.get() else {
+                return;
+            };
+
+            let statement = statements.statement(&format!(
+                "SELECT ?resource
+                WHERE {{
+                    GRAPH <{CCM_GRAPH}> {{
+                        ?resource a ?type .
+                        VALUES ?type {{ ccm:Provider ccm:Collection ccm:Calendar ccm:Event }}
+                    }}
+                }}"
+            ));
+            let cursor = match statement.execute_future().await {
+                Ok(cursor) => cursor,
+                Err(err) => {
+                    warn!("Failed to load resources of Tracker store: {err}");
+                    return;
+                }
+            };
+
+            let mut uris = Vec::new();
+            loop {
+                match cursor.next_future().await {
+                    Ok(true) => uris.extend(cursor.string(0).map(|uri| uri.to_string())),
+                    Ok(false) => break,
+                    Err(err) => {
+                        warn!("Failed to load resources of Tracker store: {err}");
+                        return;
+                    }
+                }
+            }
+            uris.sort();
+            uris.dedup();
+            info!("Loading {} resources from Tracker store", uris.len());
+
+            // Children whose parent comes in a later chunk are deferred until it does
+            for chunk in uris.chunks(STORE_LOAD_CHUNK_SIZE) {
+                let chunk = chunk.iter().map(String::as_str).collect::<Vec<_>>();
+                for pre_resource in PreResource::from_uris_future(statements, &chunk)
+                    .await
+                    .into_iter()
+                    .filter_map(Result::ok)
+                {
+                    self.apply_pre_resource(pre_resource);
+                }
+            }
+        }
+
+        /// Queue the changes of the Tracker store, and handle them unless it is already being
+        /// done.
+        fn queue_notifier_events(&self, events: &[NotifierEvent]) {
+            self.store_changes.borrow_mut().extend(
+                events
+                    .iter()
+                    .filter_map(|event| Some((event.event_type(), event.urn()?.to_string()))),
+            );
+
+            if !self.handling_store_changes.replace(true) {
+                spawn!(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    async move {
+                        imp.handle_store_changes().await;
+                    }
+                ));
+            }
+        }
+
+        /// Handle the queued changes of the Tracker store, including the ones queued meanwhile.
+        async fn handle_store_changes(&self) {
+            loop {
+                let changes = self.store_changes.take();
+                if changes.is_empty() {
+                    self.handling_store_changes.set(false);
+                    return;
+                }
+                self.apply_store_changes(changes).await;
+            }
+        }
+
+        /// Create, update or delete the resources changed in the Tracker store.
+        async fn apply_store_changes(&self, changes: Vec<(NotifierEventType, String)>) {
+            let Some(statements) = self.statements.get() else {
+                return;
+            };
+
+            let mut changed = Vec::new();
+            for (event_type, uri) in changes {
+                match event_type {
+                    NotifierEventType::Create | NotifierEventType::Update => changed.push(uri),
+                    NotifierEventType::Delete => self.remove_resource(&uri),
+                    _ => {}
+                }
+            }
+            changed.sort();
+            changed.dedup();
+
+            let mut pre_resources = Vec::new();
+            for chunk in changed.chunks(STORE_LOAD_CHUNK_SIZE) {
+                let chunk = chunk.iter().map(String::as_str).collect::<Vec<_>>();
+                pre_resources.extend(
+                    PreResource::from_uris_future(statements, &chunk)
+                        .await
+                        .into_iter()
+                        .filter_map(Result::ok),
+                );
+            }
+            // Create the parents before their children
+            pre_resources.sort_by_key(|pre_resource| match pre_resource {
+                PreResource::Provider(_) => 0,
+                PreResource::Collection(_) => 1,
+                PreResource::Calendar(_) => 2,
+                PreResource::Event(_) => 3,
+            });
+            for pre_resource in pre_resources {
+                self.apply_pre_resource(pre_resource);
+            }
+        }
+
+        /// Create the resource described by a resource of the Tracker store, or update it if it
+        /// exists.
+        ///
+        /// Resources whose parent does not exist yet are deferred until it is created.
+        fn apply_pre_resource(&self, pre_resource: PreResource) {
+            let uri = pre_resource.uri().to_string();
+            let existing = self.resource_pool().get(&uri).cloned();
+            if let Some(resource) = existing {
+                pre_resource.update(&resource);
+                return;
+            }
+
+            let parent = match pre_resource.parent_uri() {
+                Some(parent_uri) => {
+                    let parent = self.resource_pool().get(parent_uri).cloned();
+                    if parent.is_none() {
+                        debug!("Deferring resource {uri} until its parent {parent_uri} exists");
+                        self.defer_pre_resource(pre_resource);
+                        return;
+                    }
+                    parent
+                }
+                None => None,
+            };
+
+            let Some(resource) = pre_resource.materialize(&self.obj(), parent.as_ref()) else {
+                return;
+            };
+            if let Resource::Collection(collection) = &resource {
+                self.collections_model().append(collection);
+            }
+            self.resource_pool().insert(uri.clone(), resource);
+
+            let children = self
+                .pending_resources
+                .borrow_mut()
+                .remove(&uri)
+                .unwrap_or_default();
+            for child in children {
+                self.apply_pre_resource(child);
+            }
+        }
+
+        /// Keep a resource until its parent is created, replacing any previous version of it.
+        fn defer_pre_resource(&self, pre_resource: PreResource) {
+            let Some(parent_uri) = pre_resource.parent_uri().map(str::to_string) else {
+                return;
+            };
+            let mut pending_resources = self.pending_resources.borrow_mut();
+            let children = pending_resources.entry(parent_uri).or_default();
+            children.retain(|child| child.uri() != pre_resource.uri());
+            children.push(pre_resource);
+        }
+
+        /// Drop a resource deleted from its backend, along with its children.
+        fn remove_resource(&self, uri: &str) {
+            for children in self.pending_resources.borrow_mut().values_mut() {
+                children.retain(|child| child.uri() != uri);
+            }
+            self.eds_calendars.borrow_mut().remove(uri);
+            let view = self.eds_views.borrow_mut().remove(uri);
+            if let Some(view) = view
+                && let Err(err) = view.dispose()
+            {
+                debug!("Failed to dispose view of {uri}: {err}");
+            }
+            self.local_calendars.borrow_mut().remove(uri);
+            let Some(resource) = self.resource_pool().remove(uri) else {
+                return;
+            };
+            match resource {
+                Resource::Provider(provider) => {
+                    let collections = provider
+                        .collections()
+                        .iter::<Collection>()
+                        .map(|collection| {
+                            collection.expect("Model should not be mutated during iteration")
+                        })
+                        .collect::<Vec<_>>();
+                    for collection in collections {
+                        self.remove_resource(&collection.uri());
+                    }
+                }
+                Resource::Collection(collection) => {
+                    let calendars = collection
+                        .calendars()
+                        .iter::<Calendar>()
+                        .map(|calendar| {
+                            calendar.expect("Model should not be mutated during iteration")
+                        })
+                        .collect::<Vec<_>>();
+                    for calendar in calendars {
+                        self.remove_resource(&calendar.uri());
+                    }
+                    let task_lists = collection
+                        .task_lists()
+                        .iter::<TaskList>()
+                        .map(|task_list| {
+                            task_list.expect("Model should not be mutated during iteration")
+                        })
+                        .collect::<Vec<_>>();
+                    for task_list in task_lists {
+                        self.remove_resource(&task_list.uri());
+                    }
+                    let memo_lists = collection
+                        .memo_lists()
+                        .iter::<MemoList>()
+                        .map(|memo_list| {
+                            memo_list.expect("Model should not be mutated during iteration")
+                        })
+                        .collect::<Vec<_>>();
+                    for memo_list in memo_lists {
+                        self.remove_resource(&memo_list.uri());
+                    }
+                    let provider_collections = collection.provider().collections();
+                    if let Some(index) = provider_collections.find(&collection) {
+                        provider_collections.remove(index);
+                    }
+                    let position = self
+                        .collections_model()
+                        .iter::<Collection>()
+                        .position(|item| item.is_ok_and(|item| item == collection));
+                    if let Some(position) = position {
+                        self.collections_model().remove(position as u32);
+                    }
+                }
+                Resource::Calendar(calendar) => {
+                    let mut resource_pool = self.resource_pool();
+                    for event in calendar.events().iter::<Event>() {
+                        let event = event.expect("Model should not be mutated during iteration");
+                        resource_pool.remove(&event.uri());
+                    }
+                    drop(resource_pool);
+                    calendar.emit_deleted();
+                }
+                Resource::Event(event) => event.emit_deleted(),
+                Resource::TaskList(task_list) => {
+                    let mut resource_pool = self.resource_pool();
+                    for task in task_list.tasks().iter::<Task>() {
+                        let task = task.expect("Model should not be mutated during iteration");
+                        resource_pool.remove(&task.uri());
+                    }
+                    drop(resource_pool);
+                    task_list.emit_deleted();
+                }
+                Resource::Task(task) => task.emit_deleted(),
+                Resource::MemoList(memo_list) => {
+                    let mut resource_pool = self.resource_pool();
+                    for memo in memo_list.memos().iter::<Memo>() {
+                        let memo = memo.expect("Model should not be mutated during iteration");
+                        resource_pool.remove(&memo.uri());
+                    }
+                    drop(resource_pool);
+                    memo_list.emit_deleted();
+                }
+                Resource::Memo(memo) => memo.emit_deleted(),
+            }
+        }
+
+        fn collections_model(&self) -> &CollectionsModel {
+            self.collections_model
+                .get()
+                .expect("collections model should be initialized")
+        }
+
+        /// Load all the events of a calendar from EDS.
+        fn load_events(&self, calendar: &Calendar, eds_calendar: &EdsCalendar) {
+            self.refresh_events(calendar, eds_calendar, &Query::All, &[]);
+        }
+
+        /// Load the events of a calendar matching a query from EDS, creating or updating them.
+        pub(super) fn refresh_events(
+            &self,
+            calendar: &Calendar,
+            eds_calendar: &EdsCalendar,
+            query: &Query,
+            vtimezones: &[Component],
+        ) {
+            let objects = match eds_calendar.get_object_list(&query.to_string()) {
+                Ok(objects) => objects,
+                Err(err) => {
+                    warn!(
+                        "Failed to load events of calendar {}: {err}",
+                        calendar.uri()
+                    );
+                    return;
+                }
+            };
+            self.apply_events(calendar, &objects, vtimezones);
+        }
+
+        /// Create or update the events of iCalendar objects in a calendar.
+        fn apply_events(&self, calendar: &Calendar, objects: &[String], vtimezones: &[Component]) {
+            let obj = self.obj();
+            for component in objects
+                .iter()
+                .flat_map(|object| components(object, "VEVENT"))
+            {
+                let Some(uid) = component.uid() else {
+                    warn!("Ignoring event without UID in calendar {}", calendar.uri());
+                    continue;
+                };
+                let uri = event_uri(&calendar.uri(), &uid, component.recurrence_id());
+                let existing = self.resource_pool().get(&uri).cloned();
+                if let Some(Resource::Event(event)) = existing {
+                    event.update_from_component(&component, vtimezones);
+                    continue;
+                }
+
+                let Some(event) = Event::from_component(&obj, calendar, &component, vtimezones)
+                else {
+                    warn!("Ignoring invalid event in calendar {}", calendar.uri());
+                    continue;
+                };
+                calendar.add_event(&event);
+                self.resource_pool()
+                    .insert(event.uri(), Resource::Event(event.clone()));
+            }
+        }
+
+        /// Load the tasks of a task list matching a query from EDS, creating or updating them.
+        pub(super) fn refresh_tasks(
+            &self,
+            task_list: &TaskList,
+            eds_task_list: &EdsCalendar,
+            query: &Query,
+        ) {
+            let objects = match eds_task_list.get_object_list(&query.to_string()) {
+                Ok(objects) => objects,
+                Err(err) => {
+                    warn!(
+                        "Failed to load tasks of task list {}: {err}",
+                        task_list.uri()
+                    );
+                    return;
+                }
+            };
+            self.apply_tasks(task_list, &objects);
+        }
+
+        /// Create or update the tasks of iCalendar objects in a task list.
+        fn apply_tasks(&self, task_list: &TaskList, objects: &[String]) {
+            let obj = self.obj();
+            for component in objects
+                .iter()
+                .flat_map(|object| components(object, "VTODO"))
+            {
+                let Some(uid) = component.uid() else {
+                    warn!("Ignoring task without UID in task list {}", task_list.uri());
+                    continue;
+                };
+                let uri = event_uri(&task_list.uri(), &uid, component.recurrence_id());
+                let existing = self.resource_pool().get(&uri).cloned();
+                if let Some(Resource::Task(task)) = existing {
+                    task.update_from_component(&component, &[]);
+                    task_list.link_task(&task);
+                    continue;
+                }
+
+                let Some(task) = Task::from_component(&obj, task_list, &component, &[]) else {
+                    continue;
+                };
+                task_list.add_task(&task);
+                self.resource_pool()
+                    .insert(task.uri(), Resource::Task(task.clone()));
+            }
+        }
+
+        /// Load the memos of a memo list matching a query from EDS, creating or updating them.
+        pub(super) fn refresh_memos(
+            &self,
+            memo_list: &MemoList,
+            eds_memo_list: &EdsCalendar,
+            query: &Query,
+        ) {
+            let objects = match eds_memo_list.get_object_list(&query.to_string()) {
+                Ok(objects) => objects,
+                Err(err) => {
+                    warn!(
+                        "Failed to load memos of memo list {}: {err}",
+                        memo_list.uri()
+                    );
+                    return;
+                }
+            };
+            self.apply_memos(memo_list, &objects);
+        }
+
+        /// Create or update the memos of iCalendar objects in a memo list.
+        fn apply_memos(&self, memo_list: &MemoList, objects: &[String]) {
+            let obj = self.obj();
+            for component in objects
+                .iter()
+                .flat_map(|object| components(object, "VJOURNAL"))
+            {
+                let Some(uid) = component.uid() else {
+                    warn!("Ignoring memo without UID in memo list {}", memo_list.uri());
+                    continue;
+                };
+                let uri = event_uri(&memo_list.uri(), &uid, component.recurrence_id());
+                let existing = self.resource_pool().get(&uri).cloned();
+                if let Some(Resource::Memo(memo)) = existing {
+                    memo.update_from_component(&component, &[]);
+                    continue;
+                }
+
+                let Some(memo) = Memo::from_component(&obj, memo_list, &component, &[]) else {
+                    continue;
+                };
+                memo_list.add_memo(&memo);
+                self.resource_pool()
+                    .insert(memo.uri(), Resource::Memo(memo.clone()));
+            }
+        }
+
+        pub(super) fn eds_calendar(&self, calendar_uri: &str) -> Option<EdsCalendar> {
+            self.eds_calendars.borrow().get(calendar_uri).cloned()
+        }
+
+        /// The busy intervals of users in all the calendars, within a window.
+        pub(super) fn free_busy(
+            &self,
+            users: &[&str],
+            window: &Interval,
+        ) -> HashMap<String, Vec<Interval>> {
+            let (start, end) = window;
+            let mut intervals = users
+                .iter()
+                .map(|user| (user.to_ascii_lowercase(), Vec::new()))
+                .collect::<HashMap<_, _>>();
+            let obj = self.obj();
+
+            for (calendar_uri, eds_calendar) in self.eds_calendars.borrow().iter() {
+                if !matches!(
+                    self.resource_pool().get(calendar_uri),
+                    Some(Resource::Calendar(_))
+                ) {
+                    continue;
+                }
+                if self.source(calendar_uri).is_some_and(|source_info| {
+                    NO_FREE_BUSY_BACKENDS.contains(&source_info.backend_name.as_str())
+                }) {
+                    continue;
+                }
+
+                if !self.local_calendars.borrow().contains(calendar_uri) {
+                    let users = users
+                        .iter()
+                        .map(|user| user.to_string())
+                        .collect::<Vec<_>>();
+                    let objects = match eds_calendar.get_free_busy(
+                        start.timestamp().as_second(),
+                        end.timestamp().as_second(),
+                        &users,
+                    ) {
+                        Ok(objects) => objects,
+                        Err(err) => {
+                            warn!("Failed to get free/busy data of calendar {calendar_uri}: {err}");
+                            continue;
+                        }
+                    };
+                    for vfreebusy in objects
+                        .iter()
+                        .flat_map(|object| components(object, "VFREEBUSY"))
+                    {
+                        let (Some(user), busy) = vfreebusy_intervals(&vfreebusy) else {
+                            continue;
+                        };
+                        if let Some(intervals) = intervals.get_mut(&user.to_ascii_lowercase()) {
+                            intervals.extend(busy);
+                        }
+                    }
+                    continue;
+                }
+
+                // Local calendars only know about the time of their owner, who is the queried user
+                // when the calendar has no address
+                let own_address = match (obj.own_address(calendar_uri), users) {
+                    (Some(own_address), _) => own_address,
+                    (None, [user]) => user.to_string(),
+                    (None, _) => continue,
+                };
+                let Some(own_intervals) = intervals.get_mut(&own_address.to_ascii_lowercase())
+                else {
+                    continue;
+                };
+                let query = Query::occur_in_time_range(&Zoned(start.clone()), &Zoned(end.clone()));
+                let objects = match eds_calendar.get_object_list(&query.to_string()) {
+                    Ok(objects) => objects,
+                    Err(err) => {
+                        warn!("Failed to query calendar {calendar_uri}: {err}");
+                        continue;
+                    }
+                };
+                let events = objects
+                    .iter()
+                    .flat_map(|object| components(object, "VEVENT"))
+                    .collect::<Vec<_>>();
+                let vtimezones = events
+                    .iter()
+                    .flat_map(Component::tzids)
+                    .collect::<HashSet<_>>()
+                    .into_iter()
+                    .filter_map(|tzid| obj.vtimezone(calendar_uri, tzid))
+                    .collect::<Vec<_>>();
+                own_intervals.extend(event_intervals(&events, &own_address, &vtimezones, window));
+            }
+
+            intervals
+                .into_iter()
+                .map(|(user, intervals)| (user, merge_intervals(intervals)))
+                .collect()
+        }
+
+        /// Search the events of the ccm store with a full-text query, best matches first.
+        ///
+        /// Returns `None` if the manager is neither backed by nor indexed into a store.
+        pub(super) async fn search_store(
+            &self,
+            text: &str,
+        ) -> Option<Result<Vec<EventMatch>, glib::Error>> {
+            let statements = self.statements.get()?;
+            let fts_query = fts_query(text);
+            if fts_query.is_empty() {
+                return Some(Ok(Vec::new()));
+            }
+            Some(self.run_search(statements, &fts_query).await)
+        }
+
+        /// Run a full-text query, resolving the matching events once all of them are read so
+        /// that the resource pool is not locked while waiting for the store.
+        async fn run_search(
+            &self,
+            statements: &StatementCache,
+            fts_query: &str,
+        ) -> Result<Vec<EventMatch>, glib::Error> {
+            let statement = statements.try_statement(&format!(
+                "SELECT ?event fts:rank(?event) fts:snippet(?event, \"{SNIPPET_MATCH_START}\", \"{SNIPPET_MATCH_END}\", \"…\", 8)
+                WHERE {{
+                    GRAPH <{CCM_GRAPH}> {{
+                        ?event a ccm:Event ;
+                            fts:match ~text .
+                    }}
+                }}
+                ORDER BY DESC(fts:rank(?event))"
+            ))?;
+            statement.bind_string("text", fts_query);
+
+            let cursor = statement.execute_future().await?;
+            let mut rows = Vec::new();
+            while cursor.next_future().await? {
+                if let Some(iri) = cursor.string(0) {
+                    let snippet = cursor.string(2).unwrap_or_default();
+                    rows.push((iri.to_string(), cursor.double(1), snippet.to_string()));
+                }
+            }
+
+            let resource_pool = self.resource_pool();
+            let matches = rows
+                .into_iter()
+                .filter_map(|(iri, rank, snippet)| {
+                    // Indexed resources are stored under the IRI of their URI
+                    let event = resource_pool
+                        .get(&iri)
+                        .or_else(|| resource_uri(&iri).and_then(|uri| resource_pool.get(&uri)));
+                    match event {
+                        Some(Resource::Event(event)) => {
+                            Some(EventMatch::new(event, rank, &snippet))
+                        }
+                        _ => None,
+                    }
+                })
+                .collect();
+            Ok(matches)
+        }
+
+        /// Find the events matching a query in all the calendars.
+        pub(super) fn query_events(&self, query: &Query) -> Vec<Event> {
+            let query = query.to_string();
+            let mut events = Vec::new();
+
+            for (calendar_uri, eds_calendar) in self.eds_calendars.borrow().iter() {
+                let objects = match eds_calendar.get_object_list(&query) {
+                    Ok(objects) => objects,
+                    Err(err) => {
+                        warn!("Failed to query calendar {calendar_uri}: {err}");
+                        continue;
+                    }
+                };
+
+                let resource_pool = self.resource_pool();
+                for component in objects
+                    .iter()
+                    .flat_map(|object| components(object, "VEVENT"))
+                {
+                    let Some(uid) = component.uid() else {
+                        continue;
+                    };
+                    let uri = event_uri(calendar_uri, &uid, component.recurrence_id());
+                    if let Some(Resource::Event(event)) = resource_pool.get(&uri) {
+                        events.push(event.clone());
+                    }
+                }
+            }
+
+            events
+        }
+    }
+
+    /// The color of a source, or blue if it has none.
+    fn source_color(source_info: &SourceInfo) -> RGBA {
+        source_info
+            .color
+            .as_deref()
+            .and_then(|color| RGBA::parse(color).ok())
+            .unwrap_or(RGBA::BLUE)
+    }
+
+    /// The email address and name of the user of a collection.
+    ///
+    /// They come from the mail identity of the account of the collection, or else from the
+    /// identity of the collection itself if it is an email address.
+    fn identity(
+        collection: &SourceInfo,
+        sources: &HashMap<String, SourceInfo>,
+    ) -> (Option<String>, Option<String>) {
+        let mail_identity = sources.values().find(|source_info| {
+            source_info.kind == SourceKind::MailIdentity
+                && source_info.address.is_some()
+                && source_info.parent.as_ref() == Some(&collection.uid)
+        });
+        match mail_identity {
+            Some(mail_identity) => (mail_identity.address.clone(), mail_identity.name.clone()),
+            None => (
+                collection
+                    .address
+                    .clone()
+                    .filter(|address| address.contains('@')),
+                None,
+            ),
+        }
+    }
+
+    /// Parse the components with the given name of an iCalendar object.
+    pub(super) fn components(object: &str, name: &str) -> Vec<Component> {
+        match Component::parse(object) {
+            Ok(component) if component.name == name => vec![component],
+            Ok(component) => component
+                .components
+                .into_iter()
+                .filter(|component| component.name == name)
+                .collect(),
+            Err(err) => {
+                warn!("Failed to parse iCalendar object: {err}");
+                Vec::new()
+            }
+        }
     }
 }
 
@@ -118,28 +1217,863 @@ impl Manager {
         glib::Object::new()
     }
 
+    /// Create a manager whose resources are also loaded from a Tracker store, and kept in sync
+    /// with it.
+    ///
+    /// Providers, collections, calendars and events created, updated or deleted in the `ccm`
+    /// graph of the store are reflected in the resource pool and the models. A store of this
+    /// process can be opened with [`open_local_store`](crate::open_local_store).
+    pub fn with_sparql_connection(sparql_connection: &SparqlConnection) -> Self {
+        let obj = Self::new();
+        obj.imp().watch_store(sparql_connection);
+        obj
+    }
+
+    /// Create a manager whose resources only come from a ccm store published on the session bus
+    /// under `bus_name`, e.g. with [`StoreEndpoint::publish`](crate::StoreEndpoint::publish).
+    ///
+    /// EDS is not used, so calendars can be browsed and kept in sync without opening them, but
+    /// changes cannot be written to the backends.
+    pub fn connect_remote(bus_name: &str) -> Result<Self, glib::Error> {
+        let sparql_connection =
+            SparqlConnection::bus_new(bus_name, None, None::<&gio::DBusConnection>)?;
+        let obj: Self = glib::Object::builder().property("use-eds", false).build();
+        obj.imp().watch_store(&sparql_connection);
+        Ok(obj)
+    }
+
+    /// Mirror the providers, collections, calendars and events of this manager into the ccm
+    /// graph of a Tracker store, so that other processes can query them with SPARQL.
+    ///
+    /// The store is kept in sync as resources are loaded from the backends, updated and
+    /// removed.
+    pub fn index_into(&self, sparql_connection: &SparqlConnection) {
+        let indexer = Indexer::new(sparql_connection);
+        if self.imp().indexer.set(indexer.clone()).is_err() {
+            warn!("Manager is already indexed into a Tracker store");
+            return;
+        }
+        indexer.watch_collections(&self.collections_model());
+        // The store is searched with the statements of the manager
+        self.imp()
+            .statements
+            .get_or_init(|| StatementCache::new(sparql_connection));
+    }
+
     pub fn find_resource(&self, uri: &str) -> Option<Resource> {
         self.imp().resource_pool().get(uri).cloned()
     }
 
-    pub(crate) fn create_calendar(&self, _collection_uri: &str, _name: &str, _color: RGBA) {
-        dbg!("TODO");
+    pub(crate) fn create_calendar(&self, collection_uri: &str, name: &str, color: RGBA) {
+        let imp = self.imp();
+        let Some(collection) = imp.source(collection_uri) else {
+            warn!("Cannot create calendar in unknown collection {collection_uri}");
+            return;
+        };
+
+        // Calendars are created with the backend of their siblings, which depends on the account
+        let backend_name = imp
+            .sources
+            .borrow()
+            .values()
+            .find(|source_info| {
+                source_info.kind == SourceKind::Calendar
+                    && source_info.parent.as_deref() == Some(collection_uri)
+            })
+            .map(|source_info| source_info.backend_name.clone())
+            .unwrap_or_else(|| {
+                if collection.remote_creatable {
+                    "caldav".to_string()
+                } else {
+                    "local".to_string()
+                }
+            });
+        let uid = glib::uuid_string_random().to_string();
+        let data = new_source_data(
+            SourceKind::Calendar,
+            name,
+            collection_uri,
+            &backend_name,
+            &color,
+        );
+        if let Err(err) = create_source(imp.connection(), &collection, &uid, &data) {
+            warn!("Failed to create calendar in collection {collection_uri}: {err}");
+            return;
+        }
+        imp.sync_sources();
+    }
+
+    pub(crate) fn update_calendar(&self, uri: &str, name: Option<&str>, color: Option<RGBA>) {
+        let imp = self.imp();
+        let Some(source_info) = imp.source(uri) else {
+            warn!("Cannot update unknown calendar {uri}");
+            return;
+        };
+
+        let result = update_source(imp.connection(), &source_info, |key_file| {
+            if let Some(name) = name {
+                key_file.set_string("Data Source", "DisplayName", name);
+            }
+            if let (Some(color), Some(group)) = (color, source_info.kind.group()) {
+                key_file.set_string(group, "Color", &color_hex(&color));
+            }
+        });
+        if let Err(err) = result {
+            warn!("Failed to update calendar {uri}: {err}");
+            return;
+        }
+        imp.sync_sources();
+    }
+
+    pub(crate) fn delete_calendar(&self, uri: &str) {
+        let imp = self.imp();
+        let Some(source_info) = imp.source(uri) else {
+            warn!("Cannot delete unknown calendar {uri}");
+            return;
+        };
+
+        if let Err(err) = remove_source(imp.connection(), &source_info) {
+            warn!("Failed to delete calendar {uri}: {err}");
+            return;
+        }
+        imp.sync_sources();
+    }
+
+    /// Create an event starting at the next full hour and lasting one hour.
+    pub(crate) fn create_event(&self, calendar_uri: &str, name: &str, description: &str) {
+        let (Some(Resource::Calendar(calendar)), Some(eds_calendar)) = (
+            self.find_resource(calendar_uri),
+            self.imp().eds_calendar(calendar_uri),
+        ) else {
+            warn!("Cannot create event in unknown calendar {calendar_uri}");
+            return;
+        };
+
+        let now = jiff::Zoned::now();
+        let hour = jiff::Span::new().hours(1);
+        let Ok((start, end)) = now
+            .with()
+            .minute(0)
+            .second(0)
+            .subsec_nanosecond(0)
+            .build()
+            .and_then(|this_hour| {
+                let start = this_hour.checked_add(hour)?;
+                let end = start.checked_add(hour)?;
+                Ok((start, end))
+            })
+        else {
+            warn!("Cannot create event in calendar {calendar_uri} at the next hour");
+            return;
+        };
+
+        let uid = glib::uuid_string_random().to_string();
+        let mut component = Component::new("VEVENT");
+        component.set_text("UID", &uid);
+        component.set_property(ical::date_time_property(
+            "DTSTAMP",
+            &now.with_time_zone(jiff::tz::TimeZone::UTC),
+            false,
+        ));
+        component.set_property(ical::date_time_property("DTSTART", &start, false));
+        component.set_property(ical::date_time_property("DTEND", &end, false));
+        component.set_text("SUMMARY", name);
+        component.set_text("DESCRIPTION", description);
+
+        if let Err(err) = eds_calendar.create_objects(&[component.to_string()]) {
+            warn!("Failed to create event in calendar {calendar_uri}: {err}");
+            return;
+        }
+        self.imp()
+            .refresh_events(&calendar, &eds_calendar, &Query::uid(&uid), &[]);
+    }
+
+    /// Store the current properties of an event in its backend.
+    pub(crate) fn update_event(&self, event: &Event) -> zbus::Result<()> {
+        let calendar = event.calendar();
+        let Some(eds_calendar) = self.imp().eds_calendar(&calendar.uri()) else {
+            return Err(zbus::Error::Failure(format!(
+                "unknown calendar {}",
+                calendar.uri()
+            )));
+        };
+
+        let component = event.to_component();
+        let result = if component.recurrence_id().is_some() {
+            eds_calendar.modify_instances(&[component.to_string()])
+        } else {
+            eds_calendar.modify_objects(&[component.to_string()])
+        };
+        // Reload the event as stored by EDS, or revert it if the update failed
+        if let Some(uid) = component.uid() {
+            self.imp()
+                .refresh_events(&calendar, &eds_calendar, &Query::uid(&uid), &[]);
+        }
+        result
+    }
+
+    /// The email address we use in a calendar, to find ourselves among attendees.
+    ///
+    /// The address known by the backend of the calendar is preferred over the identity of its
+    /// collection.
+    pub(crate) fn own_address(&self, calendar_uri: &str) -> Option<String> {
+        let backend_address = self
+            .imp()
+            .eds_calendar(calendar_uri)
+            .and_then(|eds_calendar| {
+                eds_calendar
+                    .cal_email_address()
+                    .inspect_err(|err| {
+                        warn!("Failed to get own address of calendar {calendar_uri}: {err}")
+                    })
+                    .ok()
+            });
+        if let Some(address) = backend_address.filter(|address| !address.is_empty()) {
+            return Some(email_from_address(&address));
+        }
+
+        match self.find_resource(calendar_uri)? {
+            Resource::Calendar(calendar) => calendar.collection().identity_address(),
+            Resource::TaskList(task_list) => task_list.collection().identity_address(),
+            Resource::MemoList(memo_list) => memo_list.collection().identity_address(),
+            _ => None,
+        }
+    }
+
+    /// Set our participation status in an event, store it, and send the reply to the organizer.
+    ///
+    /// Returns the recipients the backend could not deliver the reply to.
+    pub(crate) fn respond_to_event(
+        &self,
+        event: &Event,
+        status: ParticipationStatus,
+    ) -> Result<Vec<String>, ItipError> {
+        let calendar_uri = event.calendar().uri();
+        let Some(eds_calendar) = self.imp().eds_calendar(&calendar_uri) else {
+            return Err(ItipError::UnknownCalendar(calendar_uri));
+        };
+        if event.organizer().is_none() {
+            return Err(ItipError::NoOrganizer);
+        }
+        let own_address = self
+            .own_address(&calendar_uri)
+            .ok_or(ItipError::NotInvited)?;
+        let attendee = event
+            .attendees()
+            .iter::<Attendee>()
+            .map(|attendee| attendee.expect("Model should not be mutated during iteration"))
+            .find(|attendee| attendee.email().eq_ignore_ascii_case(&own_address))
+            .ok_or(ItipError::NotInvited)?;
+
+        attendee.set_participation_status(status);
+        attendee.set_rsvp(false);
+        let component = event.to_component();
+        let reply = itip::reply(&component, attendee.to_property("ATTENDEE"));
+
+        // Only reply once the response is stored, so that the organizer sees what we have
+        self.update_event(event)
+            .map_err(|err| ItipError::Backend(err.to_string()))?;
+        let (recipients, _) = eds_calendar
+            .send_objects(&reply.to_string())
+            .map_err(|err| ItipError::Backend(err.to_string()))?;
+        Ok(recipients)
+    }
+
+    /// Apply an iTIP scheduling message to the events of a calendar.
+    pub(crate) fn receive_itip(
+        &self,
+        calendar_uri: &str,
+        message: ItipMessage,
+    ) -> Result<Vec<ImportedComponent>, ItipError> {
+        let (Some(Resource::Calendar(calendar)), Some(eds_calendar)) = (
+            self.find_resource(calendar_uri),
+            self.imp().eds_calendar(calendar_uri),
+        ) else {
+            return Err(ItipError::UnknownCalendar(calendar_uri.to_string()));
+        };
+
+        let stored_event = |component: &Component| {
+            let uid = component.uid()?;
+            let uri = event_uri(calendar_uri, &uid, component.recurrence_id());
+            let master_uri = event_uri(calendar_uri, &uid, None);
+            match self
+                .find_resource(&uri)
+                .or_else(|| self.find_resource(&master_uri))
+            {
+                Some(Resource::Event(event)) => Some(event),
+                _ => None,
+            }
+        };
+        let report = |component: &Component, status| ImportedComponent {
+            uid: component.uid().unwrap_or_default(),
+            summary: component.text("SUMMARY").unwrap_or_default(),
+            status,
+        };
+
+        let ItipMessage { method, content } = message;
+        match method {
+            ItipMethod::Request => {
+                // Ignore updates older than the stored event
+                let (stale, fresh): (Vec<_>, Vec<_>) =
+                    content.components.iter().cloned().partition(|component| {
+                        stored_event(component).is_some_and(|event| {
+                            itip::sequence(component) < itip::sequence(&event.to_component())
+                        })
+                    });
+                let mut imported = self
+                    .import_components(
+                        calendar_uri,
+                        IcsContent {
+                            vtimezones: content.vtimezones,
+                            components: fresh,
+                        },
+                        UidConflictPolicy::Replace,
+                    )
+                    .map_err(|err| ItipError::Backend(err.to_string()))?;
+                imported.extend(
+                    stale
+                        .iter()
+                        .map(|component| report(component, ImportStatus::Skipped)),
+                );
+                Ok(imported)
+            }
+            ItipMethod::Cancel => Ok(content
+                .components
+                .iter()
+                .map(|component| {
+                    let Some(uid) = component.uid() else {
+                        return report(component, ImportStatus::Failed("no UID".to_string()));
+                    };
+                    if stored_event(component).is_none() {
+                        return report(component, ImportStatus::Skipped);
+                    }
+
+                    let result = match component.recurrence_id() {
+                        Some(recurrence_id) => eds_calendar
+                            .remove_instances(&[(uid.clone(), recurrence_id.to_string())]),
+                        None => eds_calendar.remove_objects(&[(uid.clone(), String::new())]),
+                    };
+                    if let Err(err) = result {
+                        return report(component, ImportStatus::Failed(err.to_string()));
+                    }
+
+                    let uri = event_uri(calendar_uri, &uid, component.recurrence_id());
+                    let removed = self
+                        .imp()
+                        .resource_pool()
+                        .extract_if(|resource_uri, _| {
+                            *resource_uri == uri
+                                || (component.recurrence_id().is_none()
+                                    && resource_uri.starts_with(&format!("{uri}/")))
+                        })
+                        .collect::<Vec<_>>();
+                    for (_, resource) in removed {
+                        if let Resource::Event(event) = resource {
+                            event.emit_deleted();
+                        }
+                    }
+                    // Cancelled instances become exceptions of the remaining event
+                    if component.recurrence_id().is_some() {
+                        self.imp().refresh_events(
+                            &calendar,
+                            &eds_calendar,
+                            &Query::uid(&uid),
+                            &content.vtimezones,
+                        );
+                    }
+                    report(component, ImportStatus::Removed)
+                })
+                .collect()),
+            ItipMethod::Reply => Ok(content
+                .components
+                .iter()
+                .map(|component| {
+                    let Some(event) = stored_event(component) else {
+                        return report(
+                            component,
+                            ImportStatus::Failed("unknown event".to_string()),
+                        );
+                    };
+
+                    for property in component
+                        .properties
+                        .iter()
+                        .filter(|property| property.name == "ATTENDEE")
+                    {
+                        let replied = Attendee::from_property(property);
+                        let attendee = event
+                            .attendees()
+                            .iter::<Attendee>()
+                            .map(|attendee| {
+                                attendee.expect("Model should not be mutated during iteration")
+                            })
+                            .find(|attendee| {
+                                attendee.email().eq_ignore_ascii_case(&replied.email())
+                            });
+                        match attendee {
+                            Some(attendee) => {
+                                attendee.set_participation_status(replied.participation_status());
+                                attendee.set_rsvp(false);
+                            }
+                            // Delegates reply without having been invited by the organizer
+                            None => event.add_attendee(&replied),
+                        }
+                    }
+                    match self.update_event(&event) {
+                        Ok(()) => report(component, ImportStatus::Replaced),
+                        Err(err) => report(component, ImportStatus::Failed(err.to_string())),
+                    }
+                })
+                .collect()),
+        }
+    }
+
+    pub(crate) fn create_task(
+        &self,
+        task_list_uri: &str,
+        name: &str,
+        description: &str,
+        due: Option<&Zoned>,
+    ) {
+        let (Some(Resource::TaskList(task_list)), Some(eds_task_list)) = (
+            self.find_resource(task_list_uri),
+            self.imp().eds_calendar(task_list_uri),
+        ) else {
+            warn!("Cannot create task in unknown task list {task_list_uri}");
+            return;
+        };
+
+        let uid = glib::uuid_string_random().to_string();
+        let mut component = Component::new("VTODO");
+        component.set_text("UID", &uid);
+        component.set_property(ical::date_time_property(
+            "DTSTAMP",
+            &jiff::Zoned::now().with_time_zone(jiff::tz::TimeZone::UTC),
+            false,
+        ));
+        component.set_text("SUMMARY", name);
+        component.set_text("DESCRIPTION", description);
+        if let Some(due) = due {
+            component.set_property(ical::date_time_property("DUE", &due.0, false));
+        }
+        component.set_text("STATUS", "NEEDS-ACTION");
+
+        if let Err(err) = eds_task_list.create_objects(&[component.to_string()]) {
+            warn!("Failed to create task in task list {task_list_uri}: {err}");
+            return;
+        }
+        self.imp()
+            .refresh_tasks(&task_list, &eds_task_list, &Query::uid(&uid));
+    }
+
+    pub(crate) fn update_task(&self, task: &Task) {
+        let task_list = task.task_list();
+        let Some(eds_task_list) = self.imp().eds_calendar(&task_list.uri()) else {
+            warn!("Cannot update task {} in unknown task list", task.uri());
+            return;
+        };
+
+        let component = task.to_component();
+        if let Err(err) = eds_task_list.modify_objects(&[component.to_string()]) {
+            warn!("Failed to update task {}: {err}", task.uri());
+        }
+        // Reload the task as stored by EDS, or revert it if the update failed
+        if let Some(uid) = component.uid() {
+            self.imp()
+                .refresh_tasks(&task_list, &eds_task_list, &Query::uid(&uid));
+        }
+    }
+
+    pub(crate) fn delete_task(&self, task: &Task, policy: SubtaskPolicy) {
+        let task_list = task.task_list();
+        let Some(eds_task_list) = self.imp().eds_calendar(&task_list.uri()) else {
+            warn!("Cannot delete task {} in unknown task list", task.uri());
+            return;
+        };
+
+        let children = task
+            .children()
+            .iter::<Task>()
+            .map(|child| child.expect("Model should not be mutated during iteration"))
+            .collect::<Vec<_>>();
+        let tasks = match policy {
+            SubtaskPolicy::Cascade => task.descendants(),
+            SubtaskPolicy::Orphan => {
+                // Detach the subtasks first, so they never point to a missing parent
+                if !children.is_empty() {
+                    let orphans = children
+                        .iter()
+                        .map(|child| {
+                            let mut component = child.to_component();
+                            component
+                                .properties
+                                .retain(|property| !is_parent_relation(property));
+                            component.to_string()
+                        })
+                        .collect::<Vec<_>>();
+                    if let Err(err) = eds_task_list.modify_objects(&orphans) {
+                        warn!("Failed to detach subtasks of task {}: {err}", task.uri());
+                        return;
+                    }
+                }
+                vec![task.clone()]
+            }
+        };
+
+        let ids = tasks
+            .iter()
+            .filter_map(|task| {
+                let component = task.to_component();
+                let recurrence_id = component.recurrence_id().unwrap_or_default().to_string();
+                Some((component.uid()?, recurrence_id))
+            })
+            .collect::<Vec<_>>();
+        if let Err(err) = eds_task_list.remove_objects(&ids) {
+            warn!("Failed to delete task {}: {err}", task.uri());
+            return;
+        }
+        for task in &tasks {
+            self.imp().resource_pool().remove(&task.uri());
+            task.emit_deleted();
+        }
+
+        if policy == SubtaskPolicy::Orphan {
+            let uids = children
+                .iter()
+                .filter_map(|child| child.uid())
+                .map(|uid| Query::uid(&uid))
+                .collect::<Vec<_>>();
+            if !uids.is_empty() {
+                self.imp()
+                    .refresh_tasks(&task_list, &eds_task_list, &Query::Or(uids));
+            }
+        }
+    }
+
+    pub(crate) fn create_memo(
+        &self,
+        memo_list_uri: &str,
+        name: &str,
+        description: &str,
+        date: Option<&Zoned>,
+    ) {
+        let (Some(Resource::MemoList(memo_list)), Some(eds_memo_list)) = (
+            self.find_resource(memo_list_uri),
+            self.imp().eds_calendar(memo_list_uri),
+        ) else {
+            warn!("Cannot create memo in unknown memo list {memo_list_uri}");
+            return;
+        };
+
+        let uid = glib::uuid_string_random().to_string();
+        let mut component = Component::new("VJOURNAL");
+        component.set_text("UID", &uid);
+        component.set_property(ical::date_time_property(
+            "DTSTAMP",
+            &jiff::Zoned::now().with_time_zone(jiff::tz::TimeZone::UTC),
+            false,
+        ));
+        component.set_text("SUMMARY", name);
+        component.set_text("DESCRIPTION", description);
+        if let Some(date) = date {
+            component.set_property(ical::date_time_property("DTSTART", &date.0, false));
+        }
+
+        if let Err(err) = eds_memo_list.create_objects(&[component.to_string()]) {
+            warn!("Failed to create memo in memo list {memo_list_uri}: {err}");
+            return;
+        }
+        self.imp()
+            .refresh_memos(&memo_list, &eds_memo_list, &Query::uid(&uid));
+    }
+
+    pub(crate) fn update_memo(&self, memo: &Memo) {
+        let memo_list = memo.memo_list();
+        let Some(eds_memo_list) = self.imp().eds_calendar(&memo_list.uri()) else {
+            warn!("Cannot update memo {} in unknown memo list", memo.uri());
+            return;
+        };
+
+        let component = memo.to_component();
+        if let Err(err) = eds_memo_list.modify_objects(&[component.to_string()]) {
+            warn!("Failed to update memo {}: {err}", memo.uri());
+        }
+        // Reload the memo as stored by EDS, or revert it if the update failed
+        if let Some(uid) = component.uid() {
+            self.imp()
+                .refresh_memos(&memo_list, &eds_memo_list, &Query::uid(&uid));
+        }
+    }
+
+    pub(crate) fn delete_memo(&self, memo: &Memo) {
+        let Some(eds_memo_list) = self.imp().eds_calendar(&memo.memo_list().uri()) else {
+            warn!("Cannot delete memo {} in unknown memo list", memo.uri());
+            return;
+        };
+
+        let component = memo.to_component();
+        let Some(uid) = component.uid() else {
+            return;
+        };
+        let recurrence_id = component.recurrence_id().unwrap_or_default().to_string();
+        if let Err(err) = eds_memo_list.remove_objects(&[(uid, recurrence_id)]) {
+            warn!("Failed to delete memo {}: {err}", memo.uri());
+            return;
+        }
+        self.imp().resource_pool().remove(&memo.uri());
+        memo.emit_deleted();
     }
 
-    pub(crate) fn update_calendar(&self, _uri: &str, _name: Option<&str>, _color: Option<RGBA>) {
-        dbg!("TODO");
+    pub(crate) fn import_components(
+        &self,
+        calendar_uri: &str,
+        content: IcsContent,
+        policy: UidConflictPolicy,
+    ) -> Result<Vec<ImportedComponent>, ImportError> {
+        let imp = self.imp();
+        let (Some(Resource::Calendar(calendar)), Some(eds_calendar)) = (
+            self.find_resource(calendar_uri),
+            imp.eds_calendar(calendar_uri),
+        ) else {
+            return Err(ImportError::UnknownCalendar(calendar_uri.to_string()));
+        };
+
+        let mut report = content
+            .components
+            .iter()
+            .map(|component| ImportedComponent {
+                uid: component.uid().unwrap_or_default(),
+                summary: component.text("SUMMARY").unwrap_or_default(),
+                status: match content.check(component) {
+                    Ok(()) => ImportStatus::Created,
+                    Err(reason) => ImportStatus::Failed(reason),
+                },
+            })
+            .collect::<Vec<_>>();
+
+        // Find which of the imported UIDs are already used in the calendar
+        let uids = report
+            .iter()
+            .filter(|imported| imported.status == ImportStatus::Created)
+            .map(|imported| imported.uid.clone())
+            .collect::<HashSet<_>>();
+        if uids.is_empty() {
+            return Ok(report);
+        }
+        let query = Query::Or(uids.iter().map(|uid| Query::uid(uid)).collect());
+        let existing_uids = match eds_calendar.get_object_list(&query.to_string()) {
+            Ok(objects) => objects
+                .iter()
+                .flat_map(|object| imp::components(object, "VEVENT"))
+                .filter_map(|component| component.uid())
+                .collect::<HashSet<_>>(),
+            Err(err) => {
+                let reason = format!("failed to look for existing events: {err}");
+                for imported in &mut report {
+                    if imported.status == ImportStatus::Created {
+                        imported.status = ImportStatus::Failed(reason.clone());
+                    }
+                }
+                return Ok(report);
+            }
+        };
+
+        let mut new_uids = HashMap::new();
+        let mut to_create = Vec::new();
+        let mut to_replace = Vec::new();
+        for (index, mut component) in content.components.iter().cloned().enumerate() {
+            let imported = &mut report[index];
+            if imported.status != ImportStatus::Created {
+                continue;
+            }
+
+            if existing_uids.contains(&imported.uid) {
+                match policy {
+                    UidConflictPolicy::Skip => {
+                        imported.status = ImportStatus::Skipped;
+                        continue;
+                    }
+                    UidConflictPolicy::Replace => {
+                        imported.status = ImportStatus::Replaced;
+                        to_replace.push((index, component));
+                        continue;
+                    }
+                    UidConflictPolicy::Duplicate => {
+                        // Instances of a recurring event must keep sharing the same UID
+                        let new_uid = new_uids
+                            .entry(imported.uid.clone())
+                            .or_insert_with(|| glib::uuid_string_random().to_string())
+                            .clone();
+                        component.set_property(ical::Property::text("UID", &new_uid));
+                        imported.uid = new_uid;
+                    }
+                }
+            }
+            to_create.push((index, component));
+        }
+
+        let components = to_create
+            .iter()
+            .chain(&to_replace)
+            .map(|(_, component)| component.clone())
+            .collect::<Vec<_>>();
+        for vtimezone in content.referenced_vtimezones(&components) {
+            if let Err(err) = eds_calendar.add_timezone(&vtimezone.to_string()) {
+                warn!("Failed to add time zone to calendar {calendar_uri}: {err}");
+            }
+        }
+
+        let serialize = |components: &[(usize, Component)]| {
+            components
+                .iter()
+                .map(|(_, component)| component.to_string())
+                .collect::<Vec<_>>()
+        };
+        if !to_create.is_empty()
+            && let Err(err) = eds_calendar.create_objects(&serialize(&to_create))
+        {
+            for (index, _) in &to_create {
+                report[*index].status = ImportStatus::Failed(err.to_string());
+            }
+        }
+        if !to_replace.is_empty()
+            && let Err(err) = eds_calendar.modify_objects(&serialize(&to_replace))
+        {
+            for (index, _) in &to_replace {
+                report[*index].status = ImportStatus::Failed(err.to_string());
+            }
+        }
+
+        let imported_uids = report
+            .iter()
+            .filter(|imported| {
+                matches!(
+                    imported.status,
+                    ImportStatus::Created | ImportStatus::Replaced
+                )
+            })
+            .map(|imported| Query::uid(&imported.uid))
+            .collect::<Vec<_>>();
+        if !imported_uids.is_empty() {
+            imp.refresh_events(
+                &calendar,
+                &eds_calendar,
+                &Query::Or(imported_uids),
+                &content.vtimezones,
+            );
+        }
+
+        Ok(report)
     }
 
-    pub(crate) fn delete_calendar(&self, _uri: &str) {
-        dbg!("TODO");
+    /// Get the VTIMEZONE definition of a TZID used in a calendar.
+    pub(crate) fn vtimezone(&self, calendar_uri: &str, tzid: &str) -> Option<Component> {
+        let eds_calendar = self.imp().eds_calendar(calendar_uri)?;
+        let vtimezone = match eds_calendar.get_timezone(tzid) {
+            Ok(vtimezone) => vtimezone,
+            Err(err) => {
+                warn!("Failed to get time zone {tzid} of calendar {calendar_uri}: {err}");
+                return None;
+            }
+        };
+        match Component::parse(&vtimezone) {
+            Ok(vtimezone) => Some(vtimezone),
+            Err(err) => {
+                warn!("Invalid time zone {tzid} in calendar {calendar_uri}: {err}");
+                None
+            }
+        }
+    }
+
+    /// The times at which users are busy, according to all the calendars.
+    ///
+    /// Remote calendars are asked for the free/busy information of all the users, while the
+    /// events of local calendars are used for the time of their owner, or of the only queried
+    /// user when they have no owner address. Transparent, cancelled and declined events do not
+    /// count as busy time, and neither do the birthday and weather calendars.
+    ///
+    /// Returns the merged busy intervals within `range` of each user, keyed by their lowercase
+    /// email address.
+    pub fn free_busy(&self, users: &[&str], range: &Timeframe) -> HashMap<String, Vec<Timeframe>> {
+        let window = (range.start().0, range.end().0);
+        self.imp()
+            .free_busy(users, &window)
+            .into_iter()
+            .map(|(user, intervals)| {
+                let timeframes = intervals
+                    .into_iter()
+                    .map(|(start, end)| Timeframe::new(false, Zoned(start), Zoned(end)))
+                    .collect();
+                (user, timeframes)
+            })
+            .collect()
+    }
+
+    /// Find times at which all the attendees of a search are free to meet.
+    ///
+    /// The busy time of the attendees comes from [`Manager::free_busy`], and the working hours
+    /// of each of them are taken in their own time zone. Returns the best slots first.
+    pub fn find_free_slots(&self, search: &SlotSearch) -> Vec<Timeframe> {
+        let busy = self.imp().free_busy(&search.emails(), search.window());
+        search
+            .find_slots(&busy)
+            .into_iter()
+            .map(|(start, end)| Timeframe::new(false, Zoned(start), Zoned(end)))
+            .collect()
+    }
+
+    /// Find the events matching a query in all the calendars.
+    pub fn query_events(&self, query: &Query) -> ListStore {
+        let store = ListStore::new::<Event>();
+        store.extend_from_slice(&self.imp().query_events(query));
+        store
+    }
+
+    /// Find the events containing the given text in any of their fields.
+    pub fn search_events(&self, query: &str) -> ListStore {
+        self.query_events(&Query::contains(QueryField::Any, query))
+    }
+
+    /// Find the events matching the words of `text` without blocking.
+    ///
+    /// When the manager is backed by or indexed into a ccm store, its full-text index is used
+    /// and the events are ranked by relevance. Otherwise, or if the store cannot be searched,
+    /// the calendars are scanned like [`Self::search_events()`] does.
+    pub async fn search_events_future(&self, text: &str) -> ListStore {
+        let matches = match self.imp().search_store(text).await {
+            Some(Ok(matches)) => matches,
+            Some(Err(err)) => {
+                warn!("Failed to search Tracker store: {err}");
+                return self.search_events(text);
+            }
+            None => return self.search_events(text),
+        };
+        let store = ListStore::new::<Event>();
+        store.extend(matches.iter().map(EventMatch::event));
+        store
     }
 
-    pub(crate) fn create_event(&self, _calendar_uri: &str, _name: &str, _description: &str) {
-        dbg!("TODO");
+    /// Find the events matching the words of `text` in the full-text index of the ccm store,
+    /// best matches first, with snippets highlighting the matches.
+    ///
+    /// The last word is matched as a prefix, so that results can be shown while typing. Returns
+    /// an error if the manager is neither backed by nor indexed into a ccm store, or if the
+    /// store cannot be searched, e.g. without full-text search support.
+    pub async fn search_event_matches(&self, text: &str) -> Result<ListStore, glib::Error> {
+        let matches = self.imp().search_store(text).await.ok_or_else(|| {
+            glib::Error::new(
+                gio::IOErrorEnum::NotSupported,
+                "Full-text search requires a ccm store",
+            )
+        })??;
+        let store = ListStore::new::<EventMatch>();
+        store.extend_from_slice(&matches);
+        Ok(store)
     }
 
-    pub fn search_events(&self, _query: &str) -> ListStore {
-        ListStore::new::<Event>()
+    /// Find the events occurring between `start` and `end`.
+    pub fn events_in_range(&self, start: &Zoned, end: &Zoned) -> ListStore {
+        self.query_events(&Query::occur_in_time_range(start, end))
     }
 }
 
@@ -0,0 +1,166 @@
+use std::{
+    cell::{Cell, OnceCell, RefCell},
+    sync::LazyLock,
+};
+
+use gdk::{
+    glib::{self, Object, closure_local, subclass::Signal},
+    prelude::*,
+    subclass::prelude::*,
+};
+
+use crate::{
+    Manager, MemoList, Zoned,
+    event::event_uri,
+    ical::{self, Component},
+};
+
+mod imp {
+
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::Memo)]
+    pub struct Memo {
+        #[property(get, construct_only)]
+        manager: OnceCell<Manager>,
+        #[property(get, construct_only)]
+        memo_list: OnceCell<MemoList>,
+        #[property(get, construct_only)]
+        uri: OnceCell<String>,
+        #[property(get, set)]
+        name: RefCell<String>,
+        #[property(get, set)]
+        description: RefCell<String>,
+        #[property(get, set)]
+        date: RefCell<Option<Zoned>>,
+        /// Whether the date has no time of day.
+        #[property(get, set)]
+        all_day: Cell<bool>,
+        #[property(get, set)]
+        categories: RefCell<Vec<String>>,
+        /// The iCalendar component this memo was created from.
+        pub(super) component: RefCell<Option<Component>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Memo {
+        const NAME: &'static str = "Memo";
+        type Type = super::Memo;
+        type ParentType = Object;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for Memo {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: LazyLock<Vec<Signal>> =
+                LazyLock::new(|| vec![Signal::builder("deleted").build()]);
+            SIGNALS.as_ref()
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct Memo(ObjectSubclass<imp::Memo>);
+}
+
+impl Memo {
+    /// Create a memo from its iCalendar component.
+    ///
+    /// Returns `None` if the component has no UID.
+    pub(crate) fn from_component(
+        manager: &Manager,
+        memo_list: &MemoList,
+        component: &Component,
+        vtimezones: &[Component],
+    ) -> Option<Self> {
+        let uid = component.uid()?;
+        let uri = event_uri(&memo_list.uri(), &uid, component.recurrence_id());
+
+        let memo: Self = glib::Object::builder()
+            .property("manager", manager)
+            .property("memo-list", memo_list)
+            .property("uri", uri)
+            .build();
+        memo.update_from_component(component, vtimezones);
+        Some(memo)
+    }
+
+    /// Update this memo from its new iCalendar component.
+    pub(crate) fn update_from_component(&self, component: &Component, vtimezones: &[Component]) {
+        let date = component
+            .property("DTSTART")
+            .and_then(|property| ical::parse_date_time(property, vtimezones));
+        self.set_name(component.text("SUMMARY").unwrap_or_default());
+        self.set_description(component.text("DESCRIPTION").unwrap_or_default());
+        self.set_all_day(date.as_ref().is_some_and(|(_, is_date)| *is_date));
+        self.set_date(date.map(|(date, _)| Zoned(date)).as_ref());
+        self.set_categories(component.text_values("CATEGORIES"));
+        self.imp().component.replace(Some(component.clone()));
+    }
+
+    /// The iCalendar component of this memo, reflecting its current properties.
+    pub(crate) fn to_component(&self) -> Component {
+        let original = self.imp().component.borrow().clone();
+        let mut component = original.clone().unwrap_or_else(|| {
+            let mut component = Component::new("VJOURNAL");
+            component.set_text("UID", &glib::uuid_string_random());
+            component
+        });
+
+        component.set_text("SUMMARY", &self.name());
+        component.set_text("DESCRIPTION", &self.description());
+
+        // Only rewrite the date if it changed, to keep its original time zone
+        let date = self.date();
+        let unchanged = original
+            .as_ref()
+            .and_then(|original| original.property("DTSTART"))
+            .and_then(|property| ical::parse_date_time(property, &[]))
+            .is_some_and(|(original, is_date)| {
+                is_date == self.all_day() && date.as_ref().is_some_and(|date| date.0 == original)
+            });
+        if !unchanged {
+            match date {
+                Some(date) => component.set_property(ical::date_time_property(
+                    "DTSTART",
+                    &date.0,
+                    self.all_day(),
+                )),
+                None => component.remove_properties("DTSTART"),
+            }
+        }
+
+        component.set_text_values("CATEGORIES", &self.categories());
+
+        component
+    }
+
+    /// Ask the backend to store the current properties of this memo.
+    pub fn update(&self) {
+        // TODO: dispatch to relevant provider instead
+        self.manager().update_memo(self);
+    }
+
+    /// Ask the backend to delete this memo.
+    pub fn delete(&self) {
+        // TODO: dispatch to relevant provider instead
+        self.manager().delete_memo(self);
+    }
+
+    /// Signal that this memo was deleted.
+    pub(super) fn emit_deleted(&self) {
+        self.emit_by_name::<()>("deleted", &[]);
+    }
+
+    /// Connect to the signal emitted when this memo is deleted.
+    pub fn connect_deleted<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "deleted",
+            true,
+            closure_local!(|obj: Self| {
+                f(&obj);
+            }),
+        )
+    }
+}
@@ -0,0 +1,133 @@
+use std::{
+    cell::{OnceCell, RefCell},
+    sync::LazyLock,
+};
+
+use gdk::{
+    RGBA,
+    gio::ListStore,
+    glib::{self, Object, clone, closure_local, subclass::Signal},
+    prelude::*,
+    subclass::prelude::*,
+};
+
+use crate::{Collection, Manager, Memo, Zoned};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::MemoList)]
+    pub struct MemoList {
+        #[property(get, construct_only)]
+        manager: OnceCell<Manager>,
+        #[property(get, construct_only)]
+        collection: OnceCell<Collection>,
+        #[property(get, construct_only)]
+        uri: OnceCell<String>,
+        #[property(get, set)]
+        name: RefCell<String>,
+        #[property(get, set)]
+        color: RefCell<Option<RGBA>>,
+        #[property(get)]
+        memos: OnceCell<ListStore>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MemoList {
+        const NAME: &'static str = "MemoList";
+        type Type = super::MemoList;
+        type ParentType = Object;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MemoList {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.memos.get_or_init(ListStore::new::<Memo>);
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: LazyLock<Vec<Signal>> =
+                LazyLock::new(|| vec![Signal::builder("deleted").build()]);
+            SIGNALS.as_ref()
+        }
+    }
+
+    impl MemoList {
+        pub fn memos(&self) -> &ListStore {
+            self.memos.get().expect("memos should be initialized")
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct MemoList(ObjectSubclass<imp::MemoList>);
+}
+
+impl MemoList {
+    /// Create a memo list from its properties.
+    pub(crate) fn new(
+        manager: &Manager,
+        collection: &Collection,
+        uri: &str,
+        name: &str,
+        color: RGBA,
+    ) -> Self {
+        glib::Object::builder()
+            .property("manager", manager)
+            .property("collection", collection)
+            .property("uri", uri)
+            .property("name", name)
+            .property("color", Some(color))
+            .build()
+    }
+
+    /// Signal that this memo list was deleted.
+    pub(super) fn emit_deleted(&self) {
+        // Memos remove themselves from the model when deleted
+        let memos = self
+            .memos()
+            .iter::<Memo>()
+            .map(|memo| memo.expect("Model should not be mutated during iteration"))
+            .collect::<Vec<_>>();
+        for memo in memos {
+            memo.emit_deleted();
+        }
+
+        self.emit_by_name::<()>("deleted", &[]);
+    }
+
+    /// Connect to the signal emitted when this memo list is deleted.
+    pub fn connect_deleted<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "deleted",
+            true,
+            closure_local!(|obj: Self| {
+                f(&obj);
+            }),
+        )
+    }
+
+    /// Add a memo to this memo list.
+    pub(crate) fn add_memo(&self, memo: &Memo) {
+        self.imp().memos().append(memo);
+
+        memo.connect_deleted(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |memo| {
+                let index = obj.memos().find(memo).expect("Memo should be found");
+                obj.memos().remove(index);
+            }
+        ));
+    }
+
+    /// Ask the backend to create a new memo in this memo list.
+    pub fn create_memo(&self, name: &str, description: &str, date: Option<&Zoned>) {
+        // TODO: dispatch to relevant provider instead
+        self.manager()
+            .create_memo(&self.uri(), name, description, date);
+    }
+}
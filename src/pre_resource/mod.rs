@@ -1,17 +1,98 @@
-use gdk::gio;
-use tracing::error;
-use tsparql::SparqlConnection;
+use std::collections::HashMap;
+
+use tracing::{error, warn};
+use tsparql::{SparqlCursor, prelude::*};
+
+use crate::{Calendar, Collection, Event, Manager, Provider, Resource};
 
 mod pre_calendar;
 mod pre_collection;
 mod pre_event;
 mod pre_provider;
+mod statement_cache;
 
+pub use self::statement_cache::StatementCache;
 use self::{
     pre_calendar::PreCalendar, pre_collection::PreCollection, pre_event::PreEvent,
     pre_provider::PreProvider,
 };
 
+/// The resource classes, with the name and rank they are selected with, the most specific first.
+const RESOURCE_TYPES: &str = "VALUES (?type ?kind ?rank) {
+    (ccm:Event \"event\" 0)
+    (ccm:Calendar \"calendar\" 1)
+    (ccm:Collection \"collection\" 2)
+    (ccm:Provider \"provider\" 3)
+}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResourceType {
+    Provider,
+    Collection,
+    Calendar,
+    Event,
+}
+
+impl ResourceType {
+    fn from_kind(kind: &str) -> Option<Self> {
+        match kind {
+            "provider" => Some(Self::Provider),
+            "collection" => Some(Self::Collection),
+            "calendar" => Some(Self::Calendar),
+            "event" => Some(Self::Event),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a URI can be written as an IRI reference in a query, i.e. it has none of the
+/// characters that would end it or break out of it.
+fn is_valid_iri(uri: &str) -> bool {
+    !uri.is_empty()
+        && !uri
+            .chars()
+            .any(|c| c <= ' ' || matches!(c, '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\'))
+}
+
+/// The IRIs of resources, to be inlined as the values of a VALUES block.
+///
+/// The URIs must be valid IRIs, and their number is bounded by the callers chunking them.
+fn iri_values(uris: &[&str]) -> String {
+    debug_assert!(uris.iter().all(|uri| is_valid_iri(uri)));
+    uris.iter()
+        .map(|uri| format!("<{uri}>"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The query selecting the types of resources, whose URIs must be valid IRIs.
+fn types_query(uris: &[&str]) -> String {
+    format!(
+        "SELECT ?resource ?kind
+        WHERE {{
+            VALUES ?resource {{ {} }}
+            ?resource a ?type .
+            {RESOURCE_TYPES}
+        }}
+        ORDER BY ?resource ?rank",
+        iri_values(uris)
+    )
+}
+
+/// Reads a row of the types query, keeping the most specific type of each resource, which comes
+/// first.
+fn read_resource_type(cursor: &SparqlCursor, resource_types: &mut HashMap<String, ResourceType>) {
+    let (Some(resource), Some(kind)) = (cursor.string(0), cursor.string(1)) else {
+        return;
+    };
+    if let Some(resource_type) = ResourceType::from_kind(&kind) {
+        resource_types
+            .entry(resource.to_string())
+            .or_insert(resource_type);
+    }
+}
+
+#[derive(Debug)]
 pub enum PreResource {
     Provider(PreProvider),
     Collection(PreCollection),
@@ -20,159 +101,223 @@ pub enum PreResource {
 }
 
 impl PreResource {
-    pub fn from_uri(read_connection: &SparqlConnection, uri: &str) -> Result<Self, ()> {
-        let is_event = {
-            let statement = read_connection
-                .query_statement(
-                    "ASK {
-                        ~uri a ccm:Event .
-                    }",
-                    None::<&gio::Cancellable>,
-                )
-                .unwrap()
-                .unwrap();
-            statement.bind_string("uri", uri);
-
-            let cursor = match statement.execute(None::<&gio::Cancellable>) {
-                Ok(cursor) => cursor,
-                Err(err) => {
-                    error!("Failed to execute query: {err}");
-                    return Err(());
+    /// Retrieves many resources at once without blocking, resolving their types in a single
+    /// query and then fetching the resources of each type in a single query.
+    ///
+    /// Returns the resources in the same order as `uris`.
+    pub async fn from_uris_future(
+        statements: &StatementCache,
+        uris: &[&str],
+    ) -> Vec<Result<Self, ()>> {
+        // The URIs are inlined in the queries, so the ones that would break out of them are
+        // rejected
+        let valid_uris = uris
+            .iter()
+            .copied()
+            .filter(|uri| {
+                let is_valid = is_valid_iri(uri);
+                if !is_valid {
+                    error!("Resource {uri:?} has an invalid URI");
                 }
-            };
+                is_valid
+            })
+            .collect::<Vec<_>>();
+        if valid_uris.is_empty() {
+            return uris.iter().map(|_| Err(())).collect();
+        }
 
-            match cursor.next(None::<&gio::Cancellable>) {
-                Ok(true) => cursor.is_boolean(0),
-                Ok(false) => {
-                    error!("resource has no type");
-                    return Err(());
-                }
-                Err(err) => {
-                    error!("Failed to fetch resource type: {err}");
-                    return Err(());
-                }
+        let connection = statements.connection();
+        let cursor = match connection.query_future(&types_query(&valid_uris)).await {
+            Ok(cursor) => cursor,
+            Err(err) => {
+                error!("Failed to execute query: {err}");
+                return uris.iter().map(|_| Err(())).collect();
             }
         };
 
-        if is_event {
-            return Ok(Self::Event(PreEvent::from_uri(read_connection, uri)?));
-        }
-
-        let is_calendar = {
-            let statement = read_connection
-                .query_statement(
-                    "ASK {
-                        ~uri a ccm:Calendar .
-                    }",
-                    None::<&gio::Cancellable>,
-                )
-                .unwrap()
-                .unwrap();
-            statement.bind_string("uri", uri);
-
-            let cursor = match statement.execute(None::<&gio::Cancellable>) {
-                Ok(cursor) => cursor,
+        let mut resource_types = HashMap::new();
+        loop {
+            match cursor.next_future().await {
+                Ok(true) => read_resource_type(&cursor, &mut resource_types),
+                Ok(false) => break,
                 Err(err) => {
-                    error!("Failed to execute query: {err}");
-                    return Err(());
+                    error!("Failed to fetch resource types: {err}");
+                    return uris.iter().map(|_| Err(())).collect();
                 }
-            };
+            }
+        }
 
-            match cursor.next(None::<&gio::Cancellable>) {
-                Ok(true) => cursor.is_boolean(0),
-                Ok(false) => {
-                    error!("resource has no type");
-                    return Err(());
-                }
-                Err(err) => {
-                    error!("Failed to fetch resource type: {err}");
-                    return Err(());
-                }
+        let mut pre_resources = HashMap::new();
+        for resource_type in [
+            ResourceType::Provider,
+            ResourceType::Collection,
+            ResourceType::Calendar,
+            ResourceType::Event,
+        ] {
+            let uris = valid_uris
+                .iter()
+                .copied()
+                .filter(|uri| resource_types.get(*uri) == Some(&resource_type))
+                .collect::<Vec<_>>();
+            if uris.is_empty() {
+                continue;
             }
-        };
 
-        if is_calendar {
-            return Ok(Self::Calendar(PreCalendar::from_uri(read_connection, uri)?));
+            let result = match resource_type {
+                ResourceType::Provider => PreProvider::from_uris_future(connection, &uris)
+                    .await
+                    .map(|found| found.into_iter().map(Self::Provider).collect::<Vec<_>>()),
+                ResourceType::Collection => PreCollection::from_uris_future(connection, &uris)
+                    .await
+                    .map(|found| found.into_iter().map(Self::Collection).collect()),
+                ResourceType::Calendar => PreCalendar::from_uris_future(connection, &uris)
+                    .await
+                    .map(|found| found.into_iter().map(Self::Calendar).collect()),
+                ResourceType::Event => PreEvent::from_uris_future(connection, &uris)
+                    .await
+                    .map(|found| found.into_iter().map(Self::Event).collect()),
+            };
+            match result {
+                Ok(found) => pre_resources.extend(
+                    found
+                        .into_iter()
+                        .map(|pre_resource| (pre_resource.uri().to_string(), pre_resource)),
+                ),
+                Err(err) => error!("Failed to fetch resources of type {resource_type:?}: {err}"),
+            }
         }
 
-        let is_collection = {
-            let statement = read_connection
-                .query_statement(
-                    "ASK {
-                        ~uri a ccm:Collection .
-                    }",
-                    None::<&gio::Cancellable>,
-                )
-                .unwrap()
-                .unwrap();
-            statement.bind_string("uri", uri);
-
-            let cursor = match statement.execute(None::<&gio::Cancellable>) {
-                Ok(cursor) => cursor,
-                Err(err) => {
-                    error!("Failed to execute query: {err}");
-                    return Err(());
+        uris.iter()
+            .map(|uri| {
+                let pre_resource = pre_resources.remove(*uri);
+                if pre_resource.is_none() && is_valid_iri(uri) {
+                    if resource_types.contains_key(*uri) {
+                        error!("Resource {uri} was created but is not found in database");
+                    } else {
+                        error!("Resource {uri} is of unknown type");
+                    }
                 }
-            };
+                pre_resource.ok_or(())
+            })
+            .collect()
+    }
 
-            match cursor.next(None::<&gio::Cancellable>) {
-                Ok(true) => cursor.is_boolean(0),
-                Ok(false) => {
-                    error!("resource has no type");
-                    return Err(());
-                }
-                Err(err) => {
-                    error!("Failed to fetch resource type: {err}");
-                    return Err(());
-                }
-            }
-        };
+    /// The URI of the resource.
+    pub fn uri(&self) -> &str {
+        match self {
+            Self::Provider(pre_provider) => &pre_provider.uri,
+            Self::Collection(pre_collection) => &pre_collection.uri,
+            Self::Calendar(pre_calendar) => &pre_calendar.uri,
+            Self::Event(pre_event) => &pre_event.uri,
+        }
+    }
 
-        if is_collection {
-            return Ok(Self::Collection(PreCollection::from_uri(
-                read_connection,
-                uri,
-            )?));
+    /// The URI of the resource this resource belongs to, if any.
+    pub fn parent_uri(&self) -> Option<&str> {
+        match self {
+            Self::Provider(_) => None,
+            Self::Collection(pre_collection) => Some(&pre_collection.provider_uri),
+            Self::Calendar(pre_calendar) => Some(&pre_calendar.collection_uri),
+            Self::Event(pre_event) => Some(&pre_event.calendar_uri),
         }
+    }
 
-        let is_provider = {
-            let statement = read_connection
-                .query_statement(
-                    "ASK {
-                        ~uri a ccm:Provider .
-                    }",
-                    None::<&gio::Cancellable>,
-                )
-                .unwrap()
-                .unwrap();
-            statement.bind_string("uri", uri);
-
-            let cursor = match statement.execute(None::<&gio::Cancellable>) {
-                Ok(cursor) => cursor,
-                Err(err) => {
-                    error!("Failed to execute query: {err}");
-                    return Err(());
-                }
-            };
+    /// Creates the model object of this resource, and adds it to its parent.
+    ///
+    /// Returns `None` if the parent is not of the expected type, or if the resource is invalid.
+    pub fn materialize(&self, manager: &Manager, parent: Option<&Resource>) -> Option<Resource> {
+        match (self, parent) {
+            (Self::Provider(pre_provider), None) => Some(Resource::Provider(Provider::new(
+                manager,
+                &pre_provider.uri,
+                &pre_provider.name,
+            ))),
+            (Self::Collection(pre_collection), Some(Resource::Provider(provider))) => {
+                let collection =
+                    Collection::new(manager, provider, &pre_collection.uri, &pre_collection.name);
+                provider.add_collection(&collection);
+                Some(Resource::Collection(collection))
+            }
+            (Self::Calendar(pre_calendar), Some(Resource::Collection(collection))) => {
+                let calendar = Calendar::new(
+                    manager,
+                    collection,
+                    &pre_calendar.uri,
+                    &pre_calendar.name,
+                    pre_calendar.color,
+                );
+                collection.add_calendar(&calendar);
+                Some(Resource::Calendar(calendar))
+            }
+            (Self::Event(pre_event), Some(Resource::Calendar(calendar))) => {
+                let Some(timeframe) = pre_event.timeframe() else {
+                    error!("Event {} has an invalid time frame", pre_event.uri);
+                    return None;
+                };
+                let event = Event::new(
+                    manager,
+                    calendar,
+                    &pre_event.uri,
+                    &pre_event.name,
+                    &pre_event.description,
+                    &timeframe,
+                );
+                event.set_location(pre_event.location.clone());
+                calendar.add_event(&event);
+                Some(Resource::Event(event))
+            }
+            _ => {
+                error!("Resource {} has a parent of the wrong type", self.uri());
+                None
+            }
+        }
+    }
 
-            match cursor.next(None::<&gio::Cancellable>) {
-                Ok(true) => cursor.is_boolean(0),
-                Ok(false) => {
-                    error!("resource has no type");
-                    return Err(());
-                }
-                Err(err) => {
-                    error!("Failed to fetch resource type: {err}");
-                    return Err(());
+    /// Updates the model object of this resource with its new properties.
+    pub fn update(&self, resource: &Resource) {
+        match (self, resource) {
+            (Self::Provider(pre_provider), Resource::Provider(provider)) => {
+                provider.set_name(pre_provider.name.clone());
+            }
+            (Self::Collection(pre_collection), Resource::Collection(collection)) => {
+                collection.set_name(pre_collection.name.clone());
+            }
+            (Self::Calendar(pre_calendar), Resource::Calendar(calendar)) => {
+                calendar.emit_updated(&pre_calendar.name, pre_calendar.color);
+            }
+            (Self::Event(pre_event), Resource::Event(event)) => {
+                event.set_name(pre_event.name.clone());
+                event.set_description(pre_event.description.clone());
+                event.set_location(pre_event.location.clone());
+                match pre_event.timeframe() {
+                    Some(timeframe) => event.set_timeframe(Some(&timeframe)),
+                    None => warn!("Event {} has an invalid time frame", pre_event.uri),
                 }
             }
-        };
-
-        if is_provider {
-            return Ok(Self::Provider(PreProvider::from_uri(read_connection, uri)?));
+            _ => error!("Resource {} changed type", self.uri()),
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_iris() {
+        assert!(is_valid_iri(
+            "urn:uuid:0f8fad5b-d9cb-469f-a165-70867728950e"
+        ));
+        assert!(is_valid_iri("eds:calendar/work?id=1#event"));
+        assert!(!is_valid_iri(""));
+        assert!(!is_valid_iri("urn:a> } DELETE WHERE { ?s ?p ?o } #"));
+        assert!(!is_valid_iri("urn:a b"));
+        assert!(!is_valid_iri("urn:a\nb"));
+        assert!(!is_valid_iri("urn:a\\u003E"));
+    }
 
-        error!("Resource is of unknown type");
-        Err(())
+    #[test]
+    fn inlines_iris() {
+        assert_eq!(iri_values(&["urn:a", "urn:b"]), "<urn:a> <urn:b>");
     }
 }
@@ -1,7 +1,25 @@
-use gdk::{RGBA, gio};
+use gdk::{RGBA, glib};
 use tracing::error;
-use tsparql::{SparqlConnection, prelude::*};
+use tsparql::{SparqlConnection, SparqlCursor, prelude::*};
 
+use super::iri_values;
+
+/// The query selecting the properties of calendars, whose URIs must be valid IRIs.
+fn query(uris: &[&str]) -> String {
+    format!(
+        "SELECT ?resource ?name ?color ?collection
+        WHERE {{
+            VALUES ?resource {{ {} }}
+            ?resource a ccm:Calendar ;
+                ccm:collection ?collection ;
+                ccm:calendarName ?name ;
+                ccm:color ?color .
+        }}",
+        iri_values(uris)
+    )
+}
+
+#[derive(Debug)]
 pub struct PreCalendar {
     pub uri: String,
     pub collection_uri: String,
@@ -10,71 +28,47 @@ pub struct PreCalendar {
 }
 
 impl PreCalendar {
-    /// Retrieves a calendar resource from a URI.
-    ///
-    /// # Panics
+    /// Retrieves calendar resources from their URIs in a single query without blocking.
     ///
-    /// This function may panic if the given URI is invalid or does not point to a calendar resource.
-    pub fn from_uri(read_connection: &SparqlConnection, uri: &str) -> Result<Self, ()> {
-        let statement = read_connection
-            .query_statement(
-                "SELECT ?name ?color ?collection
-                WHERE {
-                    ~uri a ccm:Calendar ;
-                        ccm:collection ?collection ;
-                        ccm:calendarName ?name ;
-                        ccm:color ?color .
-                }",
-                None::<&gio::Cancellable>,
-            )
-            .expect("SPARQL should be valid")
-            .expect("SPARQL should be valid");
-        statement.bind_string("uri", uri);
-
-        let cursor = match statement.execute(None::<&gio::Cancellable>) {
-            Ok(cursor) => cursor,
-            Err(err) => {
-                error!("Failed to create calendar: {err:?}");
-                return Err(());
-            }
-        };
-
-        match cursor.next(None::<&gio::Cancellable>) {
-            Ok(true) => {
-                let calendar_name = cursor
-                    .string(0)
-                    .expect("Query should return a calendar name");
-                let calendar_color = match cursor
-                    .string(1)
-                    .expect("Query should return a calendar color")
-                    .parse()
-                {
-                    Ok(color) => color,
-                    Err(e) => {
-                        error!("Invalid color value for calendar {calendar_name}: {e}");
-                        return Err(());
-                    }
-                };
-                let collection_uri = cursor
-                    .string(2)
-                    .expect("Query should return a collection URI");
-                let calendar = Self {
-                    uri: uri.to_string(),
-                    collection_uri: collection_uri.to_string(),
-                    name: calendar_name.to_string(),
-                    color: calendar_color,
-                };
+    /// The URIs must be valid IRIs, and the ones not pointing to a valid calendar are left out.
+    pub async fn from_uris_future(
+        connection: &SparqlConnection,
+        uris: &[&str],
+    ) -> Result<Vec<Self>, glib::Error> {
+        let cursor = connection.query_future(&query(uris)).await?;
+        let mut calendars = Vec::new();
+        while cursor.next_future().await? {
+            calendars.extend(Self::from_cursor(&cursor));
+        }
+        Ok(calendars)
+    }
 
-                Ok(calendar)
-            }
-            Ok(false) => {
-                error!("Resource {uri} was created but is not found in database");
-                Err(())
-            }
+    fn from_cursor(cursor: &SparqlCursor) -> Option<Self> {
+        let uri = cursor
+            .string(0)
+            .expect("Query should return a calendar URI");
+        let calendar_name = cursor
+            .string(1)
+            .expect("Query should return a calendar name");
+        let calendar_color = match cursor
+            .string(2)
+            .expect("Query should return a calendar color")
+            .parse()
+        {
+            Ok(color) => color,
             Err(e) => {
-                error!("Encountered glib error: {}", e);
-                Err(())
+                error!("Invalid color value for calendar {calendar_name}: {e}");
+                return None;
             }
-        }
+        };
+        let collection_uri = cursor
+            .string(3)
+            .expect("Query should return a collection URI");
+        Some(Self {
+            uri: uri.to_string(),
+            collection_uri: collection_uri.to_string(),
+            name: calendar_name.to_string(),
+            color: calendar_color,
+        })
     }
 }
@@ -1,5 +1,23 @@
-use tsparql::SparqlConnection;
+use gdk::glib;
+use tsparql::{SparqlConnection, SparqlCursor, prelude::*};
 
+use super::iri_values;
+
+/// The query selecting the properties of collections, whose URIs must be valid IRIs.
+fn query(uris: &[&str]) -> String {
+    format!(
+        "SELECT ?resource ?name ?provider
+        WHERE {{
+            VALUES ?resource {{ {} }}
+            ?resource a ccm:Collection ;
+                ccm:provider ?provider ;
+                ccm:collectionName ?name .
+        }}",
+        iri_values(uris)
+    )
+}
+
+#[derive(Debug)]
 pub struct PreCollection {
     pub uri: String,
     pub provider_uri: String,
@@ -7,12 +25,38 @@ pub struct PreCollection {
 }
 
 impl PreCollection {
-    /// Retrieves a collection resource from a URI.
+    /// Retrieves collection resources from their URIs in a single query without blocking.
     ///
-    /// # Panics
-    ///
-    /// This function may panic if the given URI is invalid or does not point to a collection resource.
-    pub fn from_uri(_read_connection: &SparqlConnection, _uri: &str) -> Result<Self, ()> {
-        todo!()
+    /// The URIs must be valid IRIs, and the ones not pointing to a collection are left out.
+    pub async fn from_uris_future(
+        connection: &SparqlConnection,
+        uris: &[&str],
+    ) -> Result<Vec<Self>, glib::Error> {
+        let cursor = connection.query_future(&query(uris)).await?;
+        let mut collections = Vec::new();
+        while cursor.next_future().await? {
+            collections.push(Self::from_cursor(&cursor));
+        }
+        Ok(collections)
+    }
+
+    fn from_cursor(cursor: &SparqlCursor) -> Self {
+        let uri = cursor
+            .string(0)
+            .expect("Query should return a collection URI")
+            .to_string();
+        let name = cursor
+            .string(1)
+            .expect("Query should return a collection name")
+            .to_string();
+        let provider_uri = cursor
+            .string(2)
+            .expect("Query should return a provider URI")
+            .to_string();
+        Self {
+            uri,
+            provider_uri,
+            name,
+        }
     }
 }
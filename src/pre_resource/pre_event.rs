@@ -1,93 +1,120 @@
-use gdk::gio;
-use tracing::error;
-use tsparql::{SparqlConnection, prelude::*};
+use gdk::glib;
+use jiff::tz::TimeZone;
+use tsparql::{SparqlConnection, SparqlCursor, prelude::*};
 
+use super::iri_values;
+use crate::{Timeframe, Zoned};
+
+/// The query selecting the properties of events, whose URIs must be valid IRIs.
+fn query(uris: &[&str]) -> String {
+    format!(
+        "SELECT ?resource ?name ?description ?calendar ?all_day ?start ?end ?location
+        WHERE {{
+            VALUES ?resource {{ {} }}
+            ?resource a ccm:Event ;
+                ccm:calendar ?calendar ;
+                ccm:eventName ?name ;
+                ccm:eventDescription ?description  ;
+                ccm:eventAllDay ?all_day ;
+                ccm:eventStart ?start ;
+                ccm:eventEnd ?end .
+            OPTIONAL {{ ?resource ccm:eventLocation ?location }}
+        }}",
+        iri_values(uris)
+    )
+}
+
+#[derive(Debug)]
 pub struct PreEvent {
     pub uri: String,
     pub calendar_uri: String,
     pub name: String,
     pub description: String,
+    pub location: String,
     pub all_day: bool,
     pub start: String,
     pub end: String,
 }
 
 impl PreEvent {
-    /// Retrieves an event resource from a URI.
+    /// Retrieves event resources from their URIs in a single query without blocking.
     ///
-    /// # Panics
-    ///
-    /// This function may panic if the given URI is invalid or does not point to an event resource.
-    pub fn from_uri(read_connection: &SparqlConnection, uri: &str) -> Result<Self, ()> {
-        let statement = read_connection
-            .query_statement(
-                "SELECT ?name ?description ?calendar ?all_day ?start ?end
-                WHERE {
-                    ~uri a ccm:Event ;
-                        ccm:calendar ?calendar ;
-                        ccm:eventName ?name ;
-                        ccm:eventDescription ?description  ;
-                        ccm:eventAllDay ?all_day ;
-                        ccm:eventStart ?start ;
-                        ccm:eventEnd ?end .
-                }",
-                None::<&gio::Cancellable>,
-            )
-            .expect("SPARQL should be valid")
-            .expect("SPARQL should be valid");
-        statement.bind_string("uri", uri);
+    /// The URIs must be valid IRIs, and the ones not pointing to an event are left out.
+    pub async fn from_uris_future(
+        connection: &SparqlConnection,
+        uris: &[&str],
+    ) -> Result<Vec<Self>, glib::Error> {
+        let cursor = connection.query_future(&query(uris)).await?;
+        let mut events = Vec::new();
+        while cursor.next_future().await? {
+            events.push(Self::from_cursor(&cursor));
+        }
+        Ok(events)
+    }
 
-        let cursor = match statement.execute(None::<&gio::Cancellable>) {
-            Ok(cursor) => cursor,
-            Err(err) => {
-                error!("Failed to create event: {err:?}");
-                return Err(());
-            }
-        };
+    fn from_cursor(cursor: &SparqlCursor) -> Self {
+        let uri = cursor
+            .string(0)
+            .expect("Query should return an event URI")
+            .to_string();
+        let name = cursor
+            .string(1)
+            .expect("Query should return an event name")
+            .to_string();
+        let description = cursor
+            .string(2)
+            .expect("Query should return an event description")
+            .to_string();
+        let calendar_uri = cursor
+            .string(3)
+            .expect("Query should return a calendar URI")
+            .to_string();
+        let all_day = cursor.is_boolean(4);
+        let start = cursor
+            .string(5)
+            .expect("Query should return an event start")
+            .to_string();
+        let end = cursor
+            .string(6)
+            .expect("Query should return an event end")
+            .to_string();
+        let location = cursor.string(7).unwrap_or_default().to_string();
+        Self {
+            uri,
+            calendar_uri,
+            name,
+            description,
+            location,
+            all_day,
+            start,
+            end,
+        }
+    }
 
-        match cursor.next(None::<&gio::Cancellable>) {
-            Ok(true) => {
-                let name = cursor
-                    .string(0)
-                    .expect("Query should return an event name")
-                    .to_string();
-                let description = cursor
-                    .string(1)
-                    .expect("Query should return an event description")
-                    .to_string();
-                let calendar_uri = cursor
-                    .string(2)
-                    .expect("Query should return a calendar URI")
-                    .to_string();
-                let all_day = cursor.is_boolean(3);
-                let start = cursor
-                    .string(4)
-                    .expect("Query should return a calendar URI")
-                    .to_string();
-                let end = cursor
-                    .string(5)
-                    .expect("Query should return a calendar URI")
-                    .to_string();
-                let calendar = Self {
-                    uri: uri.to_string(),
-                    calendar_uri,
-                    name,
-                    description,
-                    all_day,
-                    start,
-                    end,
-                };
+    /// The time frame of the event, or `None` if its start or end is invalid.
+    pub fn timeframe(&self) -> Option<Timeframe> {
+        let start = parse_date_time(&self.start, self.all_day)?;
+        let end = parse_date_time(&self.end, self.all_day)?;
+        Some(Timeframe::new(self.all_day, start, end))
+    }
+}
 
-                Ok(calendar)
-            }
-            Ok(false) => {
-                error!("Resource {uri} was created but is not found in database");
-                Err(())
-            }
-            Err(e) => {
-                error!("Encountered glib error: {}", e);
-                Err(())
-            }
-        }
+/// Parses a date or date-time as stored in the database.
+///
+/// Dates are stored as midnight UTC, and are floating, so they are interpreted in the system
+/// time zone.
+fn parse_date_time(value: &str, is_date: bool) -> Option<Zoned> {
+    if is_date && let Some(Ok(date)) = value.get(..10).map(str::parse::<jiff::civil::Date>) {
+        return date.to_zoned(TimeZone::system()).ok().map(Zoned);
     }
+    value
+        .parse::<jiff::Zoned>()
+        .ok()
+        .or_else(|| {
+            value
+                .parse::<jiff::Timestamp>()
+                .ok()
+                .map(|timestamp| timestamp.to_zoned(TimeZone::system()))
+        })
+        .map(Zoned)
 }
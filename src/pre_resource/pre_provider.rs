@@ -1,17 +1,52 @@
-use tsparql::SparqlConnection;
+use gdk::glib;
+use tsparql::{SparqlConnection, SparqlCursor, prelude::*};
 
+use super::iri_values;
+
+/// The query selecting the properties of providers, whose URIs must be valid IRIs.
+fn query(uris: &[&str]) -> String {
+    format!(
+        "SELECT ?resource ?name
+        WHERE {{
+            VALUES ?resource {{ {} }}
+            ?resource a ccm:Provider ;
+                ccm:providerName ?name .
+        }}",
+        iri_values(uris)
+    )
+}
+
+#[derive(Debug)]
 pub struct PreProvider {
     pub uri: String,
     pub name: String,
 }
 
 impl PreProvider {
-    /// Retrieves a provider resource from a URI.
+    /// Retrieves provider resources from their URIs in a single query without blocking.
     ///
-    /// # Panics
-    ///
-    /// This function may panic if the given URI is invalid or does not point to a provider resource.
-    pub fn from_uri(_read_connection: &SparqlConnection, _uri: &str) -> Result<Self, ()> {
-        todo!()
+    /// The URIs must be valid IRIs, and the ones not pointing to a provider are left out.
+    pub async fn from_uris_future(
+        connection: &SparqlConnection,
+        uris: &[&str],
+    ) -> Result<Vec<Self>, glib::Error> {
+        let cursor = connection.query_future(&query(uris)).await?;
+        let mut providers = Vec::new();
+        while cursor.next_future().await? {
+            providers.push(Self::from_cursor(&cursor));
+        }
+        Ok(providers)
+    }
+
+    fn from_cursor(cursor: &SparqlCursor) -> Self {
+        let uri = cursor
+            .string(0)
+            .expect("Query should return a provider URI")
+            .to_string();
+        let name = cursor
+            .string(1)
+            .expect("Query should return a provider name")
+            .to_string();
+        Self { uri, name }
     }
 }
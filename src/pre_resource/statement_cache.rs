@@ -0,0 +1,59 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use gdk::{gio, glib};
+use tsparql::{SparqlConnection, SparqlStatement, prelude::*};
+
+/// The prepared statements of a connection, so that each query is only prepared once.
+///
+/// Statements are rebound before each execution, and the values bound when a query is executed
+/// are kept by it, so a statement can be reused while an earlier execution is still running.
+#[derive(Debug)]
+pub struct StatementCache {
+    read_connection: SparqlConnection,
+    statements: RefCell<HashMap<String, SparqlStatement>>,
+}
+
+impl StatementCache {
+    pub fn new(read_connection: &SparqlConnection) -> Self {
+        Self {
+            read_connection: read_connection.clone(),
+            statements: Default::default(),
+        }
+    }
+
+    /// The connection the statements are prepared on, to run queries that are not worth caching.
+    pub fn connection(&self) -> &SparqlConnection {
+        &self.read_connection
+    }
+
+    /// The prepared statement of a query, preparing it on first use.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the query is invalid.
+    pub fn statement(&self, sparql: &str) -> SparqlStatement {
+        self.try_statement(sparql).expect("SPARQL should be valid")
+    }
+
+    /// The prepared statement of a query, preparing it on first use, or the error preparing it,
+    /// e.g. if the store lacks a feature the query relies on.
+    pub fn try_statement(&self, sparql: &str) -> Result<SparqlStatement, glib::Error> {
+        if let Some(statement) = self.statements.borrow().get(sparql) {
+            return Ok(statement.clone());
+        }
+
+        let statement = self
+            .read_connection
+            .query_statement(sparql, None::<&gio::Cancellable>)?
+            .ok_or_else(|| {
+                glib::Error::new(
+                    gio::IOErrorEnum::Failed,
+                    "Failed to prepare SPARQL statement",
+                )
+            })?;
+        self.statements
+            .borrow_mut()
+            .insert(sparql.to_string(), statement.clone());
+        Ok(statement)
+    }
+}
@@ -0,0 +1,243 @@
+use std::{fmt, ops};
+
+use crate::Zoned;
+
+/// A field of a calendar component that can be searched with [`Query::contains`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryField {
+    Any,
+    Summary,
+    Description,
+    Comment,
+    Location,
+    Attendee,
+    Organizer,
+}
+
+impl QueryField {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Any => "any",
+            Self::Summary => "summary",
+            Self::Description => "description",
+            Self::Comment => "comment",
+            Self::Location => "location",
+            Self::Attendee => "attendee",
+            Self::Organizer => "organizer",
+        }
+    }
+}
+
+/// A query on calendar components, rendered as an EDS s-expression.
+///
+/// Queries are combined with [`Query::and`], [`Query::or`] and the `!` operator, and rendered with
+/// their [`Display`](fmt::Display) implementation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    /// Matches every component.
+    All,
+    /// Matches no component.
+    None,
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+    /// Matches components with an occurrence overlapping the given range.
+    OccurInTimeRange(Zoned, Zoned),
+    /// Matches components whose field contains the given text, case-insensitively.
+    Contains(QueryField, String),
+    /// Matches components with all of the given categories, or without any category if empty.
+    HasCategories(Vec<String>),
+    /// Matches components with at least one alarm.
+    HasAlarms,
+    /// Matches the component with the given UID.
+    Uid(String),
+    /// Matches completed tasks.
+    IsCompleted,
+}
+
+impl Query {
+    /// Create a query matching components occurring between `start` and `end`.
+    pub fn occur_in_time_range(start: &Zoned, end: &Zoned) -> Self {
+        Self::OccurInTimeRange(start.clone(), end.clone())
+    }
+
+    /// Create a query matching components whose `field` contains `text`.
+    pub fn contains(field: QueryField, text: &str) -> Self {
+        Self::Contains(field, text.to_owned())
+    }
+
+    /// Create a query matching components with all of the given categories.
+    pub fn has_categories<S: AsRef<str>>(categories: &[S]) -> Self {
+        Self::HasCategories(categories.iter().map(|c| c.as_ref().to_owned()).collect())
+    }
+
+    /// Create a query matching the component with the given UID.
+    pub fn uid(uid: &str) -> Self {
+        Self::Uid(uid.to_owned())
+    }
+
+    /// Combine this query with another one, matching components matched by both.
+    pub fn and(self, other: Query) -> Self {
+        match self {
+            Self::And(mut queries) => {
+                queries.push(other);
+                Self::And(queries)
+            }
+            query => Self::And(vec![query, other]),
+        }
+    }
+
+    /// Combine this query with another one, matching components matched by either.
+    pub fn or(self, other: Query) -> Self {
+        match self {
+            Self::Or(mut queries) => {
+                queries.push(other);
+                Self::Or(queries)
+            }
+            query => Self::Or(vec![query, other]),
+        }
+    }
+}
+
+impl ops::Not for Query {
+    type Output = Self;
+
+    /// Negate this query.
+    fn not(self) -> Self {
+        match self {
+            Self::Not(query) => *query,
+            query => Self::Not(Box::new(query)),
+        }
+    }
+}
+
+/// Write `s` as an s-expression string literal.
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        if matches!(c, '"' | '\\') {
+            f.write_str("\\")?;
+        }
+        write!(f, "{c}")?;
+    }
+    f.write_str("\"")
+}
+
+/// Write `zoned` as a `make-time` expression, which expects a UTC timestamp.
+fn write_time(f: &mut fmt::Formatter<'_>, zoned: &Zoned) -> fmt::Result {
+    let utc = zoned.0.timestamp().strftime("%Y%m%dT%H%M%SZ").to_string();
+    f.write_str("(make-time ")?;
+    write_string(f, &utc)?;
+    f.write_str(")")
+}
+
+fn write_list(f: &mut fmt::Formatter<'_>, operator: &str, queries: &[Query]) -> fmt::Result {
+    write!(f, "({operator}")?;
+    for query in queries {
+        write!(f, " {query}")?;
+    }
+    f.write_str(")")
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::All => f.write_str("#t"),
+            Self::None => f.write_str("#f"),
+            Self::And(queries) if queries.is_empty() => f.write_str("#t"),
+            Self::And(queries) => write_list(f, "and", queries),
+            Self::Or(queries) if queries.is_empty() => f.write_str("#f"),
+            Self::Or(queries) => write_list(f, "or", queries),
+            Self::Not(query) => write!(f, "(not {query})"),
+            Self::OccurInTimeRange(start, end) => {
+                f.write_str("(occur-in-time-range? ")?;
+                write_time(f, start)?;
+                f.write_str(" ")?;
+                write_time(f, end)?;
+                f.write_str(")")
+            }
+            Self::Contains(field, text) => {
+                f.write_str("(contains? ")?;
+                write_string(f, field.as_str())?;
+                f.write_str(" ")?;
+                write_string(f, text)?;
+                f.write_str(")")
+            }
+            Self::HasCategories(categories) if categories.is_empty() => {
+                f.write_str("(has-categories? #f)")
+            }
+            Self::HasCategories(categories) => {
+                f.write_str("(has-categories?")?;
+                for category in categories {
+                    f.write_str(" ")?;
+                    write_string(f, category)?;
+                }
+                f.write_str(")")
+            }
+            Self::HasAlarms => f.write_str("(has-alarms?)"),
+            Self::Uid(uid) => {
+                f.write_str("(uid? ")?;
+                write_string(f, uid)?;
+                f.write_str(")")
+            }
+            Self::IsCompleted => f.write_str("(is-completed?)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zoned(s: &str) -> Zoned {
+        Zoned(s.parse().unwrap())
+    }
+
+    #[test]
+    fn escapes_strings() {
+        let query = Query::contains(QueryField::Summary, r#"say "hi" \ bye"#);
+        assert_eq!(
+            query.to_string(),
+            r#"(contains? "summary" "say \"hi\" \\ bye")"#
+        );
+    }
+
+    #[test]
+    fn combines_queries() {
+        let query = Query::uid("a")
+            .and(Query::HasAlarms)
+            .and(Query::has_categories(&["Work"]))
+            .or(!Query::IsCompleted);
+        assert_eq!(
+            query.to_string(),
+            r#"(or (and (uid? "a") (has-alarms?) (has-categories? "Work")) (not (is-completed?)))"#
+        );
+    }
+
+    #[test]
+    fn cancels_double_negation() {
+        assert_eq!(!!Query::HasAlarms, Query::HasAlarms);
+    }
+
+    #[test]
+    fn renders_empty_queries() {
+        assert_eq!(Query::And(Vec::new()).to_string(), "#t");
+        assert_eq!(Query::Or(Vec::new()).to_string(), "#f");
+        assert_eq!(
+            Query::has_categories::<&str>(&[]).to_string(),
+            "(has-categories? #f)"
+        );
+    }
+
+    #[test]
+    fn renders_time_range_in_utc() {
+        let query = Query::occur_in_time_range(
+            &zoned("2024-05-01T10:00:00+02:00[Europe/Paris]"),
+            &zoned("2024-05-02T00:00:00-04:00[America/New_York]"),
+        );
+        assert_eq!(
+            query.to_string(),
+            r#"(occur-in-time-range? (make-time "20240501T080000Z") (make-time "20240502T040000Z"))"#
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Calendar, Collection, Event, Provider};
+use crate::{Calendar, Collection, Event, Memo, MemoList, Provider, Task, TaskList};
 
 #[derive(Debug, Clone)]
 pub enum Resource {
@@ -6,4 +6,8 @@ pub enum Resource {
     Collection(Collection),
     Calendar(Calendar),
     Event(Event),
+    TaskList(TaskList),
+    Task(Task),
+    MemoList(MemoList),
+    Memo(Memo),
 }
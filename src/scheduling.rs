@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use jiff::{
+    SignedDuration,
+    civil::{Time, Weekday},
+    tz::TimeZone,
+};
+
+use crate::{Timeframe, free_busy::Interval};
+
+/// The hours during which attendees can meet, in their own time zone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkingHours {
+    pub start: Time,
+    pub end: Time,
+    pub days: Vec<Weekday>,
+}
+
+impl Default for WorkingHours {
+    /// From 9:00 to 17:00, Monday to Friday.
+    fn default() -> Self {
+        Self {
+            start: Time::constant(9, 0, 0, 0),
+            end: Time::constant(17, 0, 0, 0),
+            days: vec![
+                Weekday::Monday,
+                Weekday::Tuesday,
+                Weekday::Wednesday,
+                Weekday::Thursday,
+                Weekday::Friday,
+            ],
+        }
+    }
+}
+
+/// A search for times at which attendees are all free to meet.
+#[derive(Debug, Clone)]
+pub struct SlotSearch {
+    attendees: Vec<(String, TimeZone)>,
+    duration: SignedDuration,
+    window: Interval,
+    working_hours: WorkingHours,
+    step: SignedDuration,
+    limit: usize,
+}
+
+impl SlotSearch {
+    /// Search for meetings lasting `duration` within `range`.
+    ///
+    /// By default, slots start on quarter hours within the default [`WorkingHours`], and at most
+    /// 5 of them are returned.
+    pub fn new(duration: SignedDuration, range: &Timeframe) -> Self {
+        Self {
+            attendees: Vec::new(),
+            duration,
+            window: (range.start().0, range.end().0),
+            working_hours: WorkingHours::default(),
+            step: SignedDuration::from_mins(15),
+            limit: 5,
+        }
+    }
+
+    /// Add an attendee by email address, whose working hours are in `time_zone`.
+    pub fn attendee(mut self, email: &str, time_zone: TimeZone) -> Self {
+        self.attendees.push((email.to_ascii_lowercase(), time_zone));
+        self
+    }
+
+    /// Only propose slots within these working hours of every attendee.
+    pub fn working_hours(mut self, working_hours: WorkingHours) -> Self {
+        self.working_hours = working_hours;
+        self
+    }
+
+    /// Propose slots starting at multiples of `step` past the hour.
+    pub fn step(mut self, step: SignedDuration) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Propose at most `limit` slots.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub(crate) fn emails(&self) -> Vec<&str> {
+        self.attendees
+            .iter()
+            .map(|(email, _)| email.as_str())
+            .collect()
+    }
+
+    pub(crate) fn window(&self) -> &Interval {
+        &self.window
+    }
+
+    /// The free slots of the attendees given their busy intervals, best first.
+    ///
+    /// Slots are ranked by day, then by how far they are from the edges of the working hours
+    /// of the attendee closest to them, then by start time. They do not overlap each other.
+    pub(crate) fn find_slots(&self, busy: &HashMap<String, Vec<Interval>>) -> Vec<Interval> {
+        let (window_start, window_end) = &self.window;
+        let step_seconds = self.step.as_secs().max(60);
+        if self.duration.is_negative() || self.duration.is_zero() {
+            return Vec::new();
+        }
+
+        // Align the first slot on the step, counted from the start of the hour
+        let past_hour = i64::from(window_start.minute()) * 60 + i64::from(window_start.second());
+        let to_step = (step_seconds - past_hour % step_seconds) % step_seconds;
+        let Ok(mut start) = window_start
+            .round(jiff::Unit::Second)
+            .and_then(|start| start.checked_add(SignedDuration::from_secs(to_step)))
+        else {
+            return Vec::new();
+        };
+
+        let mut candidates = Vec::new();
+        while let Ok(end) = start.checked_add(self.duration) {
+            if end > *window_end {
+                break;
+            }
+            if let Some(margin) = self.margin(&start, &end)
+                && self.is_free(busy, &start, &end)
+            {
+                candidates.push((start.clone(), end, margin));
+            }
+            let Ok(next) = start.checked_add(SignedDuration::from_secs(step_seconds)) else {
+                break;
+            };
+            start = next;
+        }
+
+        candidates.sort_by(|(a, _, a_margin), (b, _, b_margin)| {
+            a.date()
+                .cmp(&b.date())
+                .then(b_margin.cmp(a_margin))
+                .then(a.cmp(b))
+        });
+        let mut slots: Vec<Interval> = Vec::new();
+        for (start, end, _) in candidates {
+            if slots.len() >= self.limit {
+                break;
+            }
+            if slots
+                .iter()
+                .all(|(slot_start, slot_end)| end <= *slot_start || start >= *slot_end)
+            {
+                slots.push((start, end));
+            }
+        }
+        slots
+    }
+
+    /// The smallest time between a slot and the edges of the working hours of each attendee, or
+    /// `None` if it is outside the working hours of one of them.
+    fn margin(&self, start: &jiff::Zoned, end: &jiff::Zoned) -> Option<SignedDuration> {
+        let mut margin = SignedDuration::MAX;
+        for (_, time_zone) in &self.attendees {
+            let start = start.with_time_zone(time_zone.clone());
+            let end = end.with_time_zone(time_zone.clone());
+            if start.date() != end.date() && end.time() != Time::midnight() {
+                return None;
+            }
+            if !self.working_hours.days.contains(&start.weekday()) {
+                return None;
+            }
+            let end_time = if end.date() != start.date() {
+                Time::MAX
+            } else {
+                end.time()
+            };
+            if start.time() < self.working_hours.start || end_time > self.working_hours.end {
+                return None;
+            }
+            margin = margin
+                .min(start.time().duration_since(self.working_hours.start))
+                .min(self.working_hours.end.duration_since(end_time));
+        }
+        Some(margin)
+    }
+
+    /// Whether all the attendees are free during a slot.
+    fn is_free(
+        &self,
+        busy: &HashMap<String, Vec<Interval>>,
+        start: &jiff::Zoned,
+        end: &jiff::Zoned,
+    ) -> bool {
+        self.attendees.iter().all(|(email, _)| {
+            busy.get(email).is_none_or(|intervals| {
+                intervals
+                    .iter()
+                    .all(|(busy_start, busy_end)| end <= busy_start || start >= busy_end)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Zoned;
+
+    fn zoned(value: &str) -> jiff::Zoned {
+        value
+            .parse::<jiff::Timestamp>()
+            .unwrap()
+            .to_zoned(TimeZone::UTC)
+    }
+
+    fn search(duration_mins: i64, start: &str, end: &str) -> SlotSearch {
+        let range = Timeframe::new(false, Zoned(zoned(start)), Zoned(zoned(end)));
+        SlotSearch::new(SignedDuration::from_mins(duration_mins), &range)
+    }
+
+    fn interval(start: &str, end: &str) -> Interval {
+        (zoned(start), zoned(end))
+    }
+
+    #[test]
+    fn finds_free_slots_within_working_hours() {
+        let search = search(60, "2025-01-06T08:00:00Z", "2025-01-06T12:00:00Z")
+            .attendee("Jane@Example.com", TimeZone::UTC)
+            .step(SignedDuration::from_mins(30));
+        let busy = HashMap::from([(
+            "jane@example.com".to_string(),
+            vec![interval("2025-01-06T09:00:00Z", "2025-01-06T10:00:00Z")],
+        )]);
+
+        // Slots furthest from the edges of the working hours come first
+        assert_eq!(
+            search.find_slots(&busy),
+            [
+                interval("2025-01-06T11:00:00Z", "2025-01-06T12:00:00Z"),
+                interval("2025-01-06T10:00:00Z", "2025-01-06T11:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn intersects_working_hours_across_time_zones() {
+        let search = search(60, "2025-01-06T00:00:00Z", "2025-01-07T00:00:00Z")
+            .attendee("paris@example.com", TimeZone::get("Europe/Paris").unwrap())
+            .attendee(
+                "new-york@example.com",
+                TimeZone::get("America/New_York").unwrap(),
+            )
+            .step(SignedDuration::from_mins(60));
+
+        assert_eq!(
+            search.find_slots(&HashMap::new()),
+            [
+                interval("2025-01-06T14:00:00Z", "2025-01-06T15:00:00Z"),
+                interval("2025-01-06T15:00:00Z", "2025-01-06T16:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_days_off() {
+        let search = search(30, "2025-01-04T00:00:00Z", "2025-01-06T00:00:00Z")
+            .attendee("jane@example.com", TimeZone::UTC);
+        assert!(search.find_slots(&HashMap::new()).is_empty());
+
+        let search = search.working_hours(WorkingHours {
+            days: vec![Weekday::Sunday],
+            ..Default::default()
+        });
+        assert_eq!(
+            search.find_slots(&HashMap::new())[0],
+            interval("2025-01-05T12:45:00Z", "2025-01-05T13:15:00Z")
+        );
+    }
+
+    #[test]
+    fn aligns_slots_on_the_step() {
+        let search = search(30, "2025-01-06T09:07:00Z", "2025-01-06T10:00:00Z")
+            .attendee("jane@example.com", TimeZone::UTC)
+            .limit(1);
+        assert_eq!(
+            search.find_slots(&HashMap::new()),
+            [interval("2025-01-06T09:30:00Z", "2025-01-06T10:00:00Z")]
+        );
+    }
+
+    #[test]
+    fn rejects_empty_durations() {
+        let search = search(0, "2025-01-06T09:00:00Z", "2025-01-06T17:00:00Z")
+            .attendee("jane@example.com", TimeZone::UTC);
+        assert!(search.find_slots(&HashMap::new()).is_empty());
+    }
+}
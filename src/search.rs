@@ -0,0 +1,130 @@
+use std::cell::{Cell, OnceCell};
+
+use gdk::{
+    glib::{self, Object},
+    prelude::*,
+    subclass::prelude::*,
+};
+
+use crate::Event;
+
+/// The delimiters Tracker puts around the matches in snippets, which cannot appear in text.
+pub(crate) const SNIPPET_MATCH_START: &str = "\u{2}";
+pub(crate) const SNIPPET_MATCH_END: &str = "\u{3}";
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::EventMatch)]
+    pub struct EventMatch {
+        #[property(get, construct_only)]
+        event: OnceCell<Event>,
+        /// How well the event matches the search, higher being better.
+        #[property(get, construct_only)]
+        rank: Cell<f64>,
+        /// An excerpt of the event around the matches, as Pango markup with the matches in bold.
+        #[property(get, construct_only)]
+        snippet_markup: OnceCell<String>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for EventMatch {
+        const NAME: &'static str = "EventMatch";
+        type Type = super::EventMatch;
+        type ParentType = Object;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for EventMatch {}
+}
+
+glib::wrapper! {
+    /// An event found by a full-text search.
+    pub struct EventMatch(ObjectSubclass<imp::EventMatch>);
+}
+
+impl EventMatch {
+    /// Create a match from an event and the snippet returned by Tracker.
+    pub(crate) fn new(event: &Event, rank: f64, snippet: &str) -> Self {
+        glib::Object::builder()
+            .property("event", event)
+            .property("rank", rank)
+            .property("snippet-markup", snippet_markup(snippet))
+            .build()
+    }
+}
+
+/// The full-text query matching the words of `text`, the last one as a prefix for instant search.
+///
+/// Words are quoted so that the FTS query syntax in `text` is searched literally.
+pub(crate) fn fts_query(text: &str) -> String {
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    words
+        .iter()
+        .enumerate()
+        .map(|(index, word)| {
+            let word = format!("\"{}\"", word.replace('"', "\"\""));
+            if index + 1 == words.len() {
+                word + "*"
+            } else {
+                word
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Convert a snippet with delimited matches to Pango markup.
+fn snippet_markup(snippet: &str) -> String {
+    let mut parts = snippet.split(SNIPPET_MATCH_START);
+    let mut markup = glib::markup_escape_text(parts.next().unwrap_or_default()).to_string();
+    for part in parts {
+        let (matched, rest) = part.split_once(SNIPPET_MATCH_END).unwrap_or((part, ""));
+        markup.push_str("<b>");
+        markup.push_str(&glib::markup_escape_text(matched));
+        markup.push_str("</b>");
+        markup.push_str(&glib::markup_escape_text(rest));
+    }
+    markup
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_words_and_matches_last_as_prefix() {
+        assert_eq!(fts_query("team lunch"), "\"team\" \"lunch\"*");
+        assert_eq!(fts_query("  lunch "), "\"lunch\"*");
+        assert_eq!(fts_query(""), "");
+        assert_eq!(fts_query(" \t"), "");
+    }
+
+    #[test]
+    fn escapes_fts_syntax() {
+        assert_eq!(fts_query("say \"hi\""), "\"say\" \"\"\"hi\"\"\"*");
+        assert_eq!(fts_query("a OR b*"), "\"a\" \"OR\" \"b*\"*");
+    }
+
+    #[test]
+    fn converts_snippets_to_markup() {
+        assert_eq!(snippet_markup("no match"), "no match");
+        assert_eq!(
+            snippet_markup(&format!(
+                "Team {SNIPPET_MATCH_START}lunch{SNIPPET_MATCH_END} at {SNIPPET_MATCH_START}noon{SNIPPET_MATCH_END}…"
+            )),
+            "Team <b>lunch</b> at <b>noon</b>…"
+        );
+        assert_eq!(
+            snippet_markup(&format!(
+                "<Tom> & {SNIPPET_MATCH_START}Jerry's{SNIPPET_MATCH_END}"
+            )),
+            "&lt;Tom&gt; &amp; <b>Jerry&#39;s</b>"
+        );
+        assert_eq!(
+            snippet_markup(&format!("cut {SNIPPET_MATCH_START}off")),
+            "cut <b>off</b>"
+        );
+    }
+}
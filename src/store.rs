@@ -0,0 +1,106 @@
+use gdk::{gio, glib, prelude::*};
+use tracing::warn;
+use tsparql::{EndpointDBus, SparqlConnection, SparqlConnectionFlags, prelude::*};
+
+/// The ccm ontology, in Turtle.
+const ONTOLOGY: &str = include_str!("../ontology/ccm.ontology");
+
+/// The graph holding the calendar resources.
+pub const CCM_GRAPH: &str = "urn:ccm";
+
+/// A Tracker store published on D-Bus, which stays published until this is dropped.
+#[derive(Debug)]
+pub struct StoreEndpoint {
+    endpoint: EndpointDBus,
+    owner_id: Option<gio::OwnerId>,
+}
+
+impl StoreEndpoint {
+    /// Publish a Tracker store on the session bus under `bus_name`.
+    ///
+    /// Other processes can query the store with [`SparqlConnection::bus_new`], or from their own
+    /// store with `SERVICE <dbus:bus_name>`. They cannot update it, and only see the graphs of
+    /// `allowed_graphs`, e.g. [`CCM_GRAPH`].
+    pub fn publish(
+        sparql_connection: &SparqlConnection,
+        bus_name: &str,
+        allowed_graphs: &[&str],
+    ) -> Result<Self, glib::Error> {
+        let dbus_connection = gio::bus_get_sync(gio::BusType::Session, None::<&gio::Cancellable>)?;
+        let endpoint = EndpointDBus::new(
+            sparql_connection,
+            &dbus_connection,
+            None,
+            None::<&gio::Cancellable>,
+        )?;
+        endpoint.set_readonly(true);
+        endpoint.set_allowed_graphs(allowed_graphs);
+
+        let owner_id = gio::bus_own_name_on_connection(
+            &dbus_connection,
+            bus_name,
+            gio::BusNameOwnerFlags::NONE,
+            |_, _| {},
+            |_, name| warn!("Lost D-Bus name {name}"),
+        );
+
+        Ok(Self {
+            endpoint,
+            owner_id: Some(owner_id),
+        })
+    }
+
+    /// The endpoint the store is published with.
+    pub fn endpoint(&self) -> &EndpointDBus {
+        &self.endpoint
+    }
+}
+
+impl Drop for StoreEndpoint {
+    fn drop(&mut self) {
+        if let Some(owner_id) = self.owner_id.take() {
+            gio::bus_unown_name(owner_id);
+        }
+    }
+}
+
+/// Open a Tracker store with the ccm ontology in this process.
+///
+/// The store is kept in the `store` directory, or in memory if it is `None`.
+pub fn open_local_store(store: Option<&gio::File>) -> Result<SparqlConnection, glib::Error> {
+    let ontology = ontology_location()?;
+    SparqlConnection::new(
+        SparqlConnectionFlags::NONE,
+        store,
+        Some(&ontology),
+        None::<&gio::Cancellable>,
+    )
+}
+
+/// The directory holding the ccm ontology.
+///
+/// Tracker loads ontologies from a directory, so the bundled ontology is written to the user
+/// cache directory when it changes.
+fn ontology_location() -> Result<gio::File, glib::Error> {
+    let directory = gio::File::for_path(glib::user_cache_dir().join("ccm").join("ontology"));
+    if let Err(err) = directory.make_directory_with_parents(None::<&gio::Cancellable>)
+        && !err.matches(gio::IOErrorEnum::Exists)
+    {
+        return Err(err);
+    }
+
+    let file = directory.child("ccm.ontology");
+    let up_to_date = file
+        .load_contents(None::<&gio::Cancellable>)
+        .is_ok_and(|(contents, _)| &contents[..] == ONTOLOGY.as_bytes());
+    if !up_to_date {
+        file.replace_contents(
+            ONTOLOGY.as_bytes(),
+            None,
+            false,
+            gio::FileCreateFlags::REPLACE_DESTINATION,
+            None::<&gio::Cancellable>,
+        )?;
+    }
+    Ok(directory)
+}
@@ -0,0 +1,378 @@
+use std::{
+    cell::{Cell, OnceCell, RefCell},
+    sync::LazyLock,
+};
+
+use gdk::{
+    gio::ListStore,
+    glib::{self, Object, WeakRef, closure_local, subclass::Signal},
+    prelude::*,
+    subclass::prelude::*,
+};
+use jiff::tz::TimeZone;
+use tracing::warn;
+
+use crate::{
+    Manager, TaskList, Zoned,
+    event::event_uri,
+    ical::{self, Component, Property, integer},
+};
+
+/// The progress of a task, from its iCalendar STATUS.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "TaskStatus")]
+pub enum TaskStatus {
+    #[default]
+    NeedsAction,
+    InProcess,
+    Completed,
+    Cancelled,
+}
+
+impl TaskStatus {
+    fn from_ical(value: &str) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "IN-PROCESS" => Self::InProcess,
+            "COMPLETED" => Self::Completed,
+            "CANCELLED" => Self::Cancelled,
+            _ => Self::NeedsAction,
+        }
+    }
+
+    fn as_ical(self) -> &'static str {
+        match self {
+            Self::NeedsAction => "NEEDS-ACTION",
+            Self::InProcess => "IN-PROCESS",
+            Self::Completed => "COMPLETED",
+            Self::Cancelled => "CANCELLED",
+        }
+    }
+}
+
+/// What to do with the subtasks of a deleted task.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SubtaskPolicy {
+    /// Delete the subtasks, and their own subtasks, along with the task.
+    Cascade,
+    /// Keep the subtasks as top-level tasks.
+    #[default]
+    Orphan,
+}
+
+mod imp {
+
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::Task)]
+    pub struct Task {
+        #[property(get, construct_only)]
+        manager: OnceCell<Manager>,
+        #[property(get, construct_only)]
+        task_list: OnceCell<TaskList>,
+        #[property(get, construct_only)]
+        uri: OnceCell<String>,
+        #[property(get, set)]
+        name: RefCell<String>,
+        #[property(get, set)]
+        description: RefCell<String>,
+        /// Whether the start and due times are dates, without a time of day.
+        #[property(get, set)]
+        all_day: Cell<bool>,
+        #[property(get, set)]
+        start: RefCell<Option<Zoned>>,
+        #[property(get, set)]
+        due: RefCell<Option<Zoned>>,
+        #[property(get, set)]
+        completed: RefCell<Option<Zoned>>,
+        #[property(get, set, maximum = 100)]
+        percent_complete: Cell<u32>,
+        /// From 1 for the highest priority to 9 for the lowest, or 0 if undefined.
+        #[property(get, set, maximum = 9)]
+        priority: Cell<u32>,
+        #[property(get, set, builder(TaskStatus::default()))]
+        status: Cell<TaskStatus>,
+        /// The task this task is a subtask of, if it is loaded.
+        #[property(get)]
+        pub(super) parent_task: WeakRef<super::Task>,
+        /// The subtasks of this task.
+        #[property(get)]
+        children: OnceCell<ListStore>,
+        /// The UID of the task this task is a subtask of, from its RELATED-TO property.
+        ///
+        /// Only relations to tasks of the same task list are modeled, a task related to an
+        /// event or to a task of another list stays at the root of its list.
+        pub(super) parent_uid: RefCell<Option<String>>,
+        /// The iCalendar component this task was created from.
+        pub(super) component: RefCell<Option<Component>>,
+        /// The VTIMEZONEs the dates of the component were parsed with.
+        pub(super) vtimezones: RefCell<Vec<Component>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Task {
+        const NAME: &'static str = "Task";
+        type Type = super::Task;
+        type ParentType = Object;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for Task {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.children.get_or_init(ListStore::new::<super::Task>);
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: LazyLock<Vec<Signal>> =
+                LazyLock::new(|| vec![Signal::builder("deleted").build()]);
+            SIGNALS.as_ref()
+        }
+    }
+
+    impl Task {
+        pub fn children(&self) -> &ListStore {
+            self.children.get().expect("children should be initialized")
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct Task(ObjectSubclass<imp::Task>);
+}
+
+impl Task {
+    /// Create a task from its iCalendar component.
+    ///
+    /// Returns `None` if the component has no UID.
+    pub(crate) fn from_component(
+        manager: &Manager,
+        task_list: &TaskList,
+        component: &Component,
+        vtimezones: &[Component],
+    ) -> Option<Self> {
+        let uid = component.uid()?;
+        let uri = event_uri(&task_list.uri(), &uid, component.recurrence_id());
+
+        let task: Self = glib::Object::builder()
+            .property("manager", manager)
+            .property("task-list", task_list)
+            .property("uri", uri)
+            .build();
+        task.update_from_component(component, vtimezones);
+        Some(task)
+    }
+
+    /// Update this task from its new iCalendar component.
+    pub(crate) fn update_from_component(&self, component: &Component, vtimezones: &[Component]) {
+        let date_time = |name| {
+            component
+                .property(name)
+                .and_then(|property| ical::parse_date_time(property, vtimezones))
+        };
+        let start = date_time("DTSTART");
+        let due = date_time("DUE");
+
+        self.set_name(component.text("SUMMARY").unwrap_or_default());
+        self.set_description(component.text("DESCRIPTION").unwrap_or_default());
+        self.set_all_day(
+            due.as_ref()
+                .or(start.as_ref())
+                .is_some_and(|(_, is_date)| *is_date),
+        );
+        self.set_start(start.map(|(start, _)| Zoned(start)).as_ref());
+        self.set_due(due.map(|(due, _)| Zoned(due)).as_ref());
+        self.set_completed(
+            date_time("COMPLETED")
+                .map(|(completed, _)| Zoned(completed))
+                .as_ref(),
+        );
+        self.set_percent_complete(integer(component, "PERCENT-COMPLETE").min(100));
+        self.set_priority(integer(component, "PRIORITY").min(9));
+        self.set_status(
+            component
+                .text("STATUS")
+                .map(|status| TaskStatus::from_ical(&status))
+                .unwrap_or_default(),
+        );
+        self.imp().parent_uid.replace(
+            component
+                .properties
+                .iter()
+                .find(|property| is_parent_relation(property))
+                .map(Property::text_value),
+        );
+        self.imp().component.replace(Some(component.clone()));
+        self.imp().vtimezones.replace(vtimezones.to_vec());
+    }
+
+    /// The iCalendar component of this task, reflecting its current properties.
+    pub(crate) fn to_component(&self) -> Component {
+        let original = self.imp().component.borrow().clone();
+        let vtimezones = self.imp().vtimezones.borrow();
+        let mut component = original.clone().unwrap_or_else(|| {
+            let mut component = Component::new("VTODO");
+            component.set_text("UID", &glib::uuid_string_random());
+            component
+        });
+
+        component.set_text("SUMMARY", &self.name());
+        component.set_text("DESCRIPTION", &self.description());
+
+        // Only rewrite the dates that changed, to keep their original time zones
+        let all_day = self.all_day();
+        for (name, value) in [("DTSTART", self.start()), ("DUE", self.due())] {
+            let unchanged = original
+                .as_ref()
+                .and_then(|original| original.property(name))
+                .and_then(|property| ical::parse_date_time(property, &vtimezones))
+                .is_some_and(|(original, is_date)| {
+                    is_date == all_day && value.as_ref().is_some_and(|value| value.0 == original)
+                });
+            if unchanged {
+                continue;
+            }
+            match value {
+                Some(value) => {
+                    component.set_property(ical::date_time_property(name, &value.0, all_day))
+                }
+                None => component.remove_properties(name),
+            }
+        }
+
+        match self.completed() {
+            Some(completed) => component.set_property(ical::date_time_property(
+                "COMPLETED",
+                &completed.0.with_time_zone(TimeZone::UTC),
+                false,
+            )),
+            None => component.remove_properties("COMPLETED"),
+        }
+        match self.percent_complete() {
+            0 => component.remove_properties("PERCENT-COMPLETE"),
+            percent => {
+                component.set_property(Property::new("PERCENT-COMPLETE", &percent.to_string()))
+            }
+        }
+        match self.priority() {
+            0 => component.remove_properties("PRIORITY"),
+            priority => component.set_property(Property::new("PRIORITY", &priority.to_string())),
+        }
+        component.set_text("STATUS", self.status().as_ical());
+
+        component
+            .properties
+            .retain(|property| !is_parent_relation(property));
+        if let Some(parent_uid) = self.imp().parent_uid.borrow().as_deref() {
+            component
+                .properties
+                .push(Property::text("RELATED-TO", parent_uid));
+        }
+
+        component
+    }
+
+    /// Ask the backend to store the current properties of this task.
+    pub fn update(&self) {
+        // TODO: dispatch to relevant provider instead
+        self.manager().update_task(self);
+    }
+
+    /// Mark this task as completed now, and ask the backend to store it.
+    pub fn complete(&self) {
+        self.set_status(TaskStatus::Completed);
+        self.set_percent_complete(100);
+        self.set_completed(Some(&Zoned(jiff::Zoned::now())));
+        self.update();
+    }
+
+    /// Ask the backend to make this task a subtask of `parent`, or a top-level task if `None`.
+    ///
+    /// Does nothing if `parent` is this task or one of its subtasks.
+    pub fn reparent(&self, parent: Option<&Task>) {
+        if let Some(parent) = parent {
+            let mut ancestor = Some(parent.clone());
+            while let Some(task) = ancestor {
+                if task == *self {
+                    warn!("Cannot make task {} a subtask of itself", self.uri());
+                    return;
+                }
+                ancestor = task.parent_task();
+            }
+        }
+
+        self.imp().parent_uid.replace(parent.and_then(Task::uid));
+        self.update();
+    }
+
+    /// Ask the backend to delete this task, handling its subtasks according to `policy`.
+    pub fn delete(&self, policy: SubtaskPolicy) {
+        // TODO: dispatch to relevant provider instead
+        self.manager().delete_task(self, policy);
+    }
+
+    /// The UID of this task.
+    pub(crate) fn uid(&self) -> Option<String> {
+        self.imp()
+            .component
+            .borrow()
+            .as_ref()
+            .and_then(Component::uid)
+    }
+
+    /// The UID of the task this task is a subtask of, if any.
+    pub(crate) fn parent_uid(&self) -> Option<String> {
+        self.imp().parent_uid.borrow().clone()
+    }
+
+    /// Make this task a subtask of `parent` in the loaded hierarchy, without changing its
+    /// RELATED-TO property.
+    pub(crate) fn attach_to(&self, parent: Option<&Task>) {
+        if let Some(current) = self.parent_task()
+            && let Some(index) = current.children().find(self)
+        {
+            current.children().remove(index);
+        }
+        if let Some(parent) = parent {
+            parent.children().append(self);
+        }
+        self.imp().parent_task.set(parent);
+        self.notify_parent_task();
+    }
+
+    /// This task and all its subtasks, recursively, with subtasks first.
+    pub(crate) fn descendants(&self) -> Vec<Task> {
+        let mut tasks = Vec::new();
+        for child in self.children().iter::<Task>() {
+            let child = child.expect("Model should not be mutated during iteration");
+            tasks.extend(child.descendants());
+        }
+        tasks.push(self.clone());
+        tasks
+    }
+
+    /// Signal that this task was deleted.
+    pub(super) fn emit_deleted(&self) {
+        self.emit_by_name::<()>("deleted", &[]);
+    }
+
+    /// Connect to the signal emitted when this task is deleted.
+    pub fn connect_deleted<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "deleted",
+            true,
+            closure_local!(|obj: Self| {
+                f(&obj);
+            }),
+        )
+    }
+}
+
+/// Whether a property is a RELATED-TO property pointing to the parent of its component.
+pub(crate) fn is_parent_relation(property: &Property) -> bool {
+    property.name == "RELATED-TO"
+        && property
+            .param("RELTYPE")
+            .is_none_or(|reltype| reltype.eq_ignore_ascii_case("PARENT"))
+}
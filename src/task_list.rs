@@ -0,0 +1,272 @@
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::{HashMap, HashSet},
+    sync::LazyLock,
+};
+
+use gdk::{
+    RGBA,
+    gio::ListStore,
+    glib::{self, Object, clone, closure_local, subclass::Signal},
+    prelude::*,
+    subclass::prelude::*,
+};
+
+use crate::{Collection, Manager, Task, Zoned};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::TaskList)]
+    pub struct TaskList {
+        #[property(get, construct_only)]
+        manager: OnceCell<Manager>,
+        #[property(get, construct_only)]
+        collection: OnceCell<Collection>,
+        #[property(get, construct_only)]
+        uri: OnceCell<String>,
+        #[property(get, set)]
+        name: RefCell<String>,
+        #[property(get, set)]
+        color: RefCell<Option<RGBA>>,
+        /// All the tasks of this task list.
+        #[property(get)]
+        tasks: OnceCell<ListStore>,
+        /// The tasks of this task list that are not subtasks of a loaded task.
+        #[property(get)]
+        root_tasks: OnceCell<ListStore>,
+        /// The tasks of this task list, by UID, to find the parent of a task.
+        pub(super) tasks_by_uid: RefCell<HashMap<String, Task>>,
+        /// The tasks whose parent is not loaded, by UID of their parent, to adopt them once it
+        /// is.
+        pub(super) orphans: RefCell<HashMap<String, Vec<Task>>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TaskList {
+        const NAME: &'static str = "TaskList";
+        type Type = super::TaskList;
+        type ParentType = Object;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for TaskList {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.tasks.get_or_init(ListStore::new::<Task>);
+            self.root_tasks.get_or_init(ListStore::new::<Task>);
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: LazyLock<Vec<Signal>> =
+                LazyLock::new(|| vec![Signal::builder("deleted").build()]);
+            SIGNALS.as_ref()
+        }
+    }
+
+    impl TaskList {
+        pub fn tasks(&self) -> &ListStore {
+            self.tasks.get().expect("tasks should be initialized")
+        }
+
+        pub fn root_tasks(&self) -> &ListStore {
+            self.root_tasks
+                .get()
+                .expect("root tasks should be initialized")
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct TaskList(ObjectSubclass<imp::TaskList>);
+}
+
+impl TaskList {
+    /// Create a task list from its properties.
+    pub(crate) fn new(
+        manager: &Manager,
+        collection: &Collection,
+        uri: &str,
+        name: &str,
+        color: RGBA,
+    ) -> Self {
+        glib::Object::builder()
+            .property("manager", manager)
+            .property("collection", collection)
+            .property("uri", uri)
+            .property("name", name)
+            .property("color", Some(color))
+            .build()
+    }
+
+    /// Signal that this task list was deleted.
+    pub(super) fn emit_deleted(&self) {
+        // Tasks remove themselves from the model when deleted
+        let tasks = self
+            .tasks()
+            .iter::<Task>()
+            .map(|task| task.expect("Model should not be mutated during iteration"))
+            .collect::<Vec<_>>();
+        for task in tasks {
+            task.emit_deleted();
+        }
+
+        self.emit_by_name::<()>("deleted", &[]);
+    }
+
+    /// Connect to the signal emitted when this task list is deleted.
+    pub fn connect_deleted<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "deleted",
+            true,
+            closure_local!(|obj: Self| {
+                f(&obj);
+            }),
+        )
+    }
+
+    /// Add a task to this task list.
+    pub(crate) fn add_task(&self, task: &Task) {
+        let imp = self.imp();
+        imp.tasks().append(task);
+        let uid = task.uid();
+        if let Some(uid) = &uid {
+            // Instances of a recurring task share its UID, the first one loaded is the parent
+            imp.tasks_by_uid
+                .borrow_mut()
+                .entry(uid.clone())
+                .or_insert_with(|| task.clone());
+        }
+        self.place_task(task, self.loaded_parent(task));
+
+        // Adopt the subtasks that were loaded before this task, at once since they are all at
+        // the root
+        if let Some(uid) = &uid {
+            let orphans = imp.orphans.borrow_mut().remove(uid).unwrap_or_default();
+            let adopted = orphans
+                .into_iter()
+                .filter(|orphan| {
+                    // Skip the orphans that were relinked elsewhere since
+                    orphan.parent_task().is_none() && orphan.parent_uid().as_ref() == Some(uid)
+                })
+                .filter_map(|orphan| Some((self.loaded_parent(&orphan)?, orphan)))
+                .collect::<Vec<_>>();
+            if !adopted.is_empty() {
+                let orphans = adopted
+                    .iter()
+                    .map(|(_, orphan)| orphan.clone())
+                    .collect::<HashSet<_>>();
+                self.root_tasks().retain(|object| {
+                    object
+                        .downcast_ref::<Task>()
+                        .is_none_or(|task| !orphans.contains(task))
+                });
+                for (parent, orphan) in adopted {
+                    orphan.attach_to(Some(&parent));
+                }
+            }
+        }
+
+        task.connect_deleted(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |task| {
+                let index = obj.tasks().find(task).expect("Task should be found");
+                obj.tasks().remove(index);
+                obj.unlink_task(task);
+                if let Some(uid) = task.uid() {
+                    let mut tasks_by_uid = obj.imp().tasks_by_uid.borrow_mut();
+                    if tasks_by_uid.get(&uid) == Some(task) {
+                        tasks_by_uid.remove(&uid);
+                    }
+                }
+
+                // The subtasks move to the root, until their parent is loaded again
+                let children = task
+                    .children()
+                    .iter::<Task>()
+                    .map(|child| child.expect("Model should not be mutated during iteration"))
+                    .collect::<Vec<_>>();
+                for child in children {
+                    obj.link_task(&child);
+                }
+            }
+        ));
+    }
+
+    /// Place a task under its parent in the hierarchy, or at the root if its parent is not
+    /// loaded.
+    pub(crate) fn link_task(&self, task: &Task) {
+        let parent = self.loaded_parent(task);
+        if parent == task.parent_task()
+            && (parent.is_some() || self.root_tasks().find(task).is_some())
+        {
+            return;
+        }
+
+        self.unlink_task(task);
+        self.place_task(task, parent);
+    }
+
+    /// The loaded parent of a task, if any.
+    fn loaded_parent(&self, task: &Task) -> Option<Task> {
+        let parent = task
+            .parent_uid()
+            .and_then(|parent_uid| self.imp().tasks_by_uid.borrow().get(&parent_uid).cloned());
+        // Ignore relations that would make a task its own ancestor
+        parent.filter(|parent| {
+            let mut ancestor = Some(parent.clone());
+            while let Some(current) = ancestor {
+                if current == *task {
+                    return false;
+                }
+                ancestor = current.parent_task();
+            }
+            true
+        })
+    }
+
+    /// Place a task that is not in the hierarchy under `parent`, or at the root.
+    fn place_task(&self, task: &Task, parent: Option<Task>) {
+        match parent {
+            Some(parent) => task.attach_to(Some(&parent)),
+            None => {
+                self.root_tasks().append(task);
+                if let Some(parent_uid) = task.parent_uid() {
+                    self.imp()
+                        .orphans
+                        .borrow_mut()
+                        .entry(parent_uid)
+                        .or_default()
+                        .push(task.clone());
+                }
+            }
+        }
+    }
+
+    fn unlink_task(&self, task: &Task) {
+        if task.parent_task().is_some() {
+            task.attach_to(None);
+        } else if let Some(index) = self.root_tasks().find(task) {
+            self.root_tasks().remove(index);
+        }
+        let mut orphans = self.imp().orphans.borrow_mut();
+        if let Some(parent_uid) = task.parent_uid()
+            && let Some(siblings) = orphans.get_mut(&parent_uid)
+        {
+            siblings.retain(|orphan| orphan != task);
+            if siblings.is_empty() {
+                orphans.remove(&parent_uid);
+            }
+        }
+    }
+
+    /// Ask the backend to create a new task in this task list.
+    pub fn create_task(&self, name: &str, description: &str, due: Option<&Zoned>) {
+        // TODO: dispatch to relevant provider instead
+        self.manager()
+            .create_task(&self.uri(), name, description, due);
+    }
+}
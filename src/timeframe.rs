@@ -9,6 +9,9 @@ use gdk::{
     prelude::*,
     subclass::prelude::*,
 };
+use jiff::Span;
+
+use crate::ical::{self, Component};
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, glib::Boxed)]
 #[boxed_type(name = "Zoned")]
@@ -75,6 +78,25 @@ impl Timeframe {
             .property("end", end)
             .build()
     }
+
+    /// Create a time frame from the DTSTART and DTEND or DURATION of a component.
+    pub(crate) fn from_component(component: &Component, vtimezones: &[Component]) -> Option<Self> {
+        let (start, all_day) = ical::parse_date_time(component.property("DTSTART")?, vtimezones)?;
+
+        let end = if let Some(dtend) = component.property("DTEND") {
+            ical::parse_date_time(dtend, vtimezones)?.0
+        } else if let Some(duration) = component.property("DURATION") {
+            start
+                .checked_add(ical::parse_duration(&duration.value)?)
+                .ok()?
+        } else if all_day {
+            start.checked_add(Span::new().days(1)).ok()?
+        } else {
+            start.clone()
+        };
+
+        Some(Self::new(all_day, Zoned(start), Zoned(end)))
+    }
 }
 
 impl Default for Timeframe {
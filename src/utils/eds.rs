@@ -1,7 +1,45 @@
-use gdk::glib;
-use zbus::zvariant::OwnedObjectPath;
+use std::collections::HashMap;
 
-#[derive(Debug, Clone)]
+use gdk::{RGBA, glib};
+use zbus::{
+    blocking::{Connection, Proxy},
+    zvariant::OwnedObjectPath,
+};
+
+pub const SOURCES_BUS_NAME: &str = "org.gnome.evolution.dataserver.Sources5";
+pub const SOURCE_MANAGER_PATH: &str = "/org/gnome/evolution/dataserver/SourceManager";
+const SOURCE_MANAGER_INTERFACE: &str = "org.gnome.evolution.dataserver.SourceManager";
+pub const SOURCE_INTERFACE: &str = "org.gnome.evolution.dataserver.Source";
+const SOURCE_WRITABLE_INTERFACE: &str = "org.gnome.evolution.dataserver.Source.Writable";
+const SOURCE_REMOVABLE_INTERFACE: &str = "org.gnome.evolution.dataserver.Source.Removable";
+const SOURCE_REMOTE_CREATABLE_INTERFACE: &str =
+    "org.gnome.evolution.dataserver.Source.RemoteCreatable";
+
+/// The kind of data an EDS source holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    /// A source grouping other sources, like an online account or the built-in "On This Computer".
+    Collection,
+    Calendar,
+    TaskList,
+    MemoList,
+    /// The address and name a user sends mail and invitations as.
+    MailIdentity,
+}
+
+impl SourceKind {
+    /// The key file group holding the settings of this kind of source, if it holds data.
+    pub fn group(self) -> Option<&'static str> {
+        match self {
+            Self::Calendar => Some("Calendar"),
+            Self::TaskList => Some("Task List"),
+            Self::MemoList => Some("Memo List"),
+            Self::Collection | Self::MailIdentity => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub struct SourceInfo {
     pub uid: String,
@@ -9,6 +47,15 @@ pub struct SourceInfo {
     pub display_name: String,
     pub enabled: bool,
     pub backend_name: String,
+    pub kind: SourceKind,
+    pub parent: Option<String>,
+    pub color: Option<String>,
+    /// The email address of a mail identity, or the identity of the account of a collection.
+    pub address: Option<String>,
+    /// The name of the user of a mail identity.
+    pub name: Option<String>,
+    /// Whether the sources of this collection are created on its server.
+    pub remote_creatable: bool,
 }
 
 pub fn parse_source_data(path: OwnedObjectPath, uid: String, data: String) -> Option<SourceInfo> {
@@ -28,12 +75,66 @@ pub fn parse_source_data(path: OwnedObjectPath, uid: String, data: String) -> Op
         .unwrap_or_else(|_| "Unknown".into())
         .to_string();
 
+    let parent = key_file
+        .string("Data Source", "Parent")
+        .ok()
+        .map(|parent| parent.to_string())
+        .filter(|parent| !parent.is_empty());
+
+    let non_empty = |group: &str, key: &str| {
+        key_file
+            .string(group, key)
+            .ok()
+            .map(|value| value.to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let mut address = None;
+    let mut name = None;
+
     // Check what type of source this is
-    let backend_name = if key_file.has_group("Calendar") {
+    let (kind, backend_name, color) = if key_file.has_group("Calendar") {
         let backend_name = key_file
             .string("Calendar", "BackendName")
             .unwrap_or_else(|_| "unknown".into());
-        backend_name.to_string()
+        let color = key_file.string("Calendar", "Color").ok();
+        (
+            SourceKind::Calendar,
+            backend_name.to_string(),
+            color.map(|color| color.to_string()),
+        )
+    } else if key_file.has_group("Task List") {
+        let backend_name = key_file
+            .string("Task List", "BackendName")
+            .unwrap_or_else(|_| "unknown".into());
+        let color = key_file.string("Task List", "Color").ok();
+        (
+            SourceKind::TaskList,
+            backend_name.to_string(),
+            color.map(|color| color.to_string()),
+        )
+    } else if key_file.has_group("Memo List") {
+        let backend_name = key_file
+            .string("Memo List", "BackendName")
+            .unwrap_or_else(|_| "unknown".into());
+        let color = key_file.string("Memo List", "Color").ok();
+        (
+            SourceKind::MemoList,
+            backend_name.to_string(),
+            color.map(|color| color.to_string()),
+        )
+    } else if key_file.has_group("Collection") {
+        let backend_name = key_file
+            .string("Collection", "BackendName")
+            .unwrap_or_else(|_| "unknown".into());
+        address = non_empty("Collection", "Identity");
+        (SourceKind::Collection, backend_name.to_string(), None)
+    } else if key_file.has_group("Mail Identity") {
+        address = non_empty("Mail Identity", "Address");
+        name = non_empty("Mail Identity", "Name");
+        (SourceKind::MailIdentity, "none".to_string(), None)
+    } else if key_file.groups().len() == 1 {
+        // Sources without any extension only group other sources, like the built-in stubs
+        (SourceKind::Collection, "none".to_string(), None)
     } else {
         return None;
     };
@@ -44,5 +145,141 @@ pub fn parse_source_data(path: OwnedObjectPath, uid: String, data: String) -> Op
         display_name,
         enabled,
         backend_name,
+        kind,
+        parent,
+        color,
+        address,
+        name,
+        remote_creatable: false,
     })
 }
+
+/// Read a source of the registry, given the D-Bus interfaces of its object.
+///
+/// Returns `None` if the object is not a source, or if the source is disabled.
+pub fn read_source<'a>(
+    connection: &Connection,
+    path: OwnedObjectPath,
+    interfaces: impl Iterator<Item = &'a str>,
+) -> Option<SourceInfo> {
+    let mut is_source = false;
+    let mut remote_creatable = false;
+    for interface in interfaces {
+        match interface {
+            SOURCE_INTERFACE => is_source = true,
+            SOURCE_REMOTE_CREATABLE_INTERFACE => remote_creatable = true,
+            _ => {}
+        }
+    }
+    if !is_source {
+        return None;
+    }
+
+    let proxy = Proxy::new(connection, SOURCES_BUS_NAME, path.clone(), SOURCE_INTERFACE).ok()?;
+    let data = proxy.get_property::<String>("Data").ok()?;
+    let uid = proxy.get_property::<String>("UID").ok()?;
+    let mut source_info = parse_source_data(path, uid, data)?;
+    source_info.remote_creatable = remote_creatable;
+    Some(source_info)
+}
+
+/// The key file of a new calendar, task list or memo list in a collection.
+pub fn new_source_data(
+    kind: SourceKind,
+    display_name: &str,
+    parent: &str,
+    backend_name: &str,
+    color: &RGBA,
+) -> String {
+    let key_file = glib::KeyFile::new();
+    key_file.set_string("Data Source", "DisplayName", display_name);
+    key_file.set_boolean("Data Source", "Enabled", true);
+    key_file.set_string("Data Source", "Parent", parent);
+    if let Some(group) = kind.group() {
+        key_file.set_string(group, "BackendName", backend_name);
+        key_file.set_string(group, "Color", &color_hex(color));
+    }
+    key_file.to_data().to_string()
+}
+
+/// A color as written in sources, e.g. `#3465a4`.
+pub fn color_hex(color: &RGBA) -> String {
+    let channel = |value: f32| (value.clamp(0., 1.) * 255.).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.red()),
+        channel(color.green()),
+        channel(color.blue())
+    )
+}
+
+/// Create a source in a collection.
+///
+/// Collections of online accounts create their sources on the server, the others are created
+/// in the registry directly.
+pub fn create_source(
+    connection: &Connection,
+    collection: &SourceInfo,
+    uid: &str,
+    data: &str,
+) -> zbus::Result<()> {
+    if collection.remote_creatable {
+        let proxy = Proxy::new(
+            connection,
+            SOURCES_BUS_NAME,
+            collection.path.clone(),
+            SOURCE_REMOTE_CREATABLE_INTERFACE,
+        )?;
+        return proxy.call_method("Create", &(uid, data)).map(|_| ());
+    }
+
+    let proxy = Proxy::new(
+        connection,
+        SOURCES_BUS_NAME,
+        SOURCE_MANAGER_PATH,
+        SOURCE_MANAGER_INTERFACE,
+    )?;
+    let sources = HashMap::from([(uid, data)]);
+    proxy.call_method("CreateSources", &(sources,)).map(|_| ())
+}
+
+/// Change the key file of a source.
+pub fn update_source(
+    connection: &Connection,
+    source: &SourceInfo,
+    update: impl FnOnce(&glib::KeyFile),
+) -> zbus::Result<()> {
+    let proxy = Proxy::new(
+        connection,
+        SOURCES_BUS_NAME,
+        source.path.clone(),
+        SOURCE_INTERFACE,
+    )?;
+    let data = proxy.get_property::<String>("Data")?;
+    let key_file = glib::KeyFile::new();
+    key_file
+        .load_from_data(&data, glib::KeyFileFlags::KEEP_COMMENTS)
+        .map_err(|err| zbus::Error::Failure(err.to_string()))?;
+    update(&key_file);
+
+    let proxy = Proxy::new(
+        connection,
+        SOURCES_BUS_NAME,
+        source.path.clone(),
+        SOURCE_WRITABLE_INTERFACE,
+    )?;
+    proxy
+        .call_method("Write", &(key_file.to_data().as_str(),))
+        .map(|_| ())
+}
+
+/// Remove a source from the registry.
+pub fn remove_source(connection: &Connection, source: &SourceInfo) -> zbus::Result<()> {
+    let proxy = Proxy::new(
+        connection,
+        SOURCES_BUS_NAME,
+        source.path.clone(),
+        SOURCE_REMOVABLE_INTERFACE,
+    )?;
+    proxy.call_method("Remove", &()).map(|_| ())
+}
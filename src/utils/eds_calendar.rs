@@ -0,0 +1,286 @@
+use zbus::{
+    MatchRule, Message,
+    blocking::{Connection, Proxy},
+    zvariant::{ObjectPath, OwnedObjectPath},
+};
+
+const CALENDAR_FACTORY_BUS_NAME: &str = "org.gnome.evolution.dataserver.Calendar8";
+const CALENDAR_FACTORY_PATH: &str = "/org/gnome/evolution/dataserver/CalendarFactory";
+const CALENDAR_FACTORY_INTERFACE: &str = "org.gnome.evolution.dataserver.CalendarFactory";
+const CALENDAR_INTERFACE: &str = "org.gnome.evolution.dataserver.Calendar";
+const CALENDAR_VIEW_INTERFACE: &str = "org.gnome.evolution.dataserver.CalendarView";
+
+/// A calendar, task list or memo list opened in the EDS calendar factory.
+#[derive(Debug, Clone)]
+pub struct EdsCalendar {
+    proxy: Proxy<'static>,
+}
+
+impl EdsCalendar {
+    /// Open the calendar backing the given source.
+    pub fn open(connection: &Connection, source_uid: &str) -> zbus::Result<Self> {
+        Self::open_with(connection, "OpenCalendar", source_uid)
+    }
+
+    /// Open the task list backing the given source.
+    pub fn open_task_list(connection: &Connection, source_uid: &str) -> zbus::Result<Self> {
+        Self::open_with(connection, "OpenTaskList", source_uid)
+    }
+
+    /// Open the memo list backing the given source.
+    pub fn open_memo_list(connection: &Connection, source_uid: &str) -> zbus::Result<Self> {
+        Self::open_with(connection, "OpenMemoList", source_uid)
+    }
+
+    fn open_with(connection: &Connection, method: &str, source_uid: &str) -> zbus::Result<Self> {
+        let factory = Proxy::new(
+            connection,
+            CALENDAR_FACTORY_BUS_NAME,
+            CALENDAR_FACTORY_PATH,
+            CALENDAR_FACTORY_INTERFACE,
+        )?;
+        let (object_path, bus_name): (OwnedObjectPath, String) =
+            factory.call(method, &(source_uid,))?;
+
+        let proxy = Proxy::new_owned(
+            connection.clone(),
+            bus_name,
+            object_path,
+            CALENDAR_INTERFACE,
+        )?;
+        proxy.call_method("Open", &())?;
+
+        Ok(Self { proxy })
+    }
+
+    /// Get the iCalendar components matching an s-expression query.
+    pub fn get_object_list(&self, query: &str) -> zbus::Result<Vec<String>> {
+        self.proxy.call("GetObjectList", &(query,))
+    }
+
+    /// Create and start a view of the components matching an s-expression query, whose signals
+    /// notify the components added, modified and removed from now on.
+    pub fn create_view(&self, query: &str) -> zbus::Result<EdsCalendarView> {
+        let object_path: OwnedObjectPath = self.proxy.call("GetView", &(query,))?;
+        let proxy = Proxy::new_owned(
+            self.proxy.connection().clone(),
+            self.proxy.destination().to_string(),
+            object_path,
+            CALENDAR_VIEW_INTERFACE,
+        )?;
+        // The components matching initially are already loaded
+        proxy.call_method("SetFlags", &(0u32,))?;
+        proxy.call_method("Start", &())?;
+
+        Ok(EdsCalendarView { proxy })
+    }
+
+    /// Create the given iCalendar components, returning their UIDs.
+    pub fn create_objects(&self, objects: &[String]) -> zbus::Result<Vec<String>> {
+        self.proxy.call("CreateObjects", &(objects, 0u32))
+    }
+
+    /// Replace the given iCalendar components, including all their instances.
+    pub fn modify_objects(&self, objects: &[String]) -> zbus::Result<()> {
+        self.proxy
+            .call_method("ModifyObjects", &(objects, "all", 0u32))
+            .map(|_| ())
+    }
+
+    /// Replace the given detached instances of recurring components.
+    pub fn modify_instances(&self, objects: &[String]) -> zbus::Result<()> {
+        self.proxy
+            .call_method("ModifyObjects", &(objects, "this", 0u32))
+            .map(|_| ())
+    }
+
+    /// Remove the given detached instances of recurring components.
+    pub fn remove_instances(&self, ids: &[(String, String)]) -> zbus::Result<()> {
+        self.proxy
+            .call_method("RemoveObjects", &(ids, "this", 0u32))
+            .map(|_| ())
+    }
+
+    /// Remove the components with the given UIDs and recurrence IDs, including all their
+    /// instances.
+    pub fn remove_objects(&self, ids: &[(String, String)]) -> zbus::Result<()> {
+        self.proxy
+            .call_method("RemoveObjects", &(ids, "all", 0u32))
+            .map(|_| ())
+    }
+
+    /// Send an iTIP message, returning the recipients the backend could not deliver it to and
+    /// the message as it was sent.
+    pub fn send_objects(&self, object: &str) -> zbus::Result<(Vec<String>, String)> {
+        self.proxy.call("SendObjects", &(object, 0u32))
+    }
+
+    /// The email address of the owner of the calendar, if the backend knows it.
+    pub fn cal_email_address(&self) -> zbus::Result<String> {
+        self.proxy.get_property("CalEmailAddress")
+    }
+
+    /// Get the VFREEBUSY components of users between two Unix timestamps.
+    pub fn get_free_busy(
+        &self,
+        start: i64,
+        end: i64,
+        users: &[String],
+    ) -> zbus::Result<Vec<String>> {
+        self.proxy.call("GetFreeBusy", &(start, end, users))
+    }
+
+    /// Get the VTIMEZONE definition of a TZID used in the calendar.
+    pub fn get_timezone(&self, tzid: &str) -> zbus::Result<String> {
+        self.proxy.call("GetTimezone", &(tzid,))
+    }
+
+    /// Add a VTIMEZONE definition to the calendar.
+    pub fn add_timezone(&self, vtimezone: &str) -> zbus::Result<()> {
+        self.proxy
+            .call_method("AddTimezone", &(vtimezone,))
+            .map(|_| ())
+    }
+}
+
+/// A view of the components of an [`EdsCalendar`].
+///
+/// Its ObjectsAdded and ObjectsModified signals hold the iCalendar components, and its
+/// ObjectsRemoved signal their UIDs followed by a line with their recurrence ID.
+#[derive(Debug)]
+pub struct EdsCalendarView {
+    proxy: Proxy<'static>,
+}
+
+impl EdsCalendarView {
+    /// The object path the signals of this view are emitted from.
+    pub fn path(&self) -> &ObjectPath<'static> {
+        self.proxy.path()
+    }
+
+    /// Stop this view and release it in the backend.
+    pub fn dispose(&self) -> zbus::Result<()> {
+        self.proxy.call_method("Dispose", &()).map(|_| ())
+    }
+}
+
+/// The rule matching the signals of all the views of calendars.
+pub fn calendar_views_rule() -> zbus::Result<MatchRule<'static>> {
+    Ok(MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface(CALENDAR_VIEW_INTERFACE)?
+        .build())
+}
+
+/// The components changed in an [`EdsCalendarView`], as notified by one of its signals.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ViewChange {
+    /// The iCalendar components added or modified.
+    Changed(Vec<String>),
+    /// The UIDs and recurrence IDs of the components removed.
+    Removed(Vec<(String, Option<String>)>),
+}
+
+impl ViewChange {
+    /// Read the change notified by a signal of a view.
+    ///
+    /// Returns `Ok(None)` if the message is not a signal of a view about its components.
+    pub fn from_message(message: &Message) -> zbus::Result<Option<Self>> {
+        let header = message.header();
+        if header.interface().map(|interface| interface.as_str()) != Some(CALENDAR_VIEW_INTERFACE) {
+            return Ok(None);
+        }
+        let Some(member) = header.member() else {
+            return Ok(None);
+        };
+
+        match member.as_str() {
+            "ObjectsAdded" | "ObjectsModified" => {
+                Ok(Some(Self::Changed(message.body().deserialize()?)))
+            }
+            "ObjectsRemoved" => {
+                // Each removed component is its UID and recurrence ID, on separate lines
+                let ids = message.body().deserialize::<Vec<String>>()?;
+                Ok(Some(Self::Removed(
+                    ids.iter()
+                        .map(|id| {
+                            let (uid, recurrence_id) =
+                                id.split_once('\n').unwrap_or((id.as_str(), ""));
+                            (
+                                uid.to_string(),
+                                Some(recurrence_id)
+                                    .filter(|rid| !rid.is_empty())
+                                    .map(str::to_string),
+                            )
+                        })
+                        .collect(),
+                )))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VIEW_PATH: &str = "/org/gnome/evolution/dataserver/CalendarView/1/2";
+
+    fn signal(member: &str, objects: &[&str]) -> Message {
+        Message::signal(VIEW_PATH, CALENDAR_VIEW_INTERFACE, member)
+            .unwrap()
+            .build(&(objects,))
+            .unwrap()
+    }
+
+    #[test]
+    fn matches_view_signals() {
+        let rule = calendar_views_rule().unwrap();
+        assert!(rule.matches(&signal("ObjectsAdded", &[])).unwrap());
+        assert!(rule.matches(&signal("ObjectsRemoved", &[])).unwrap());
+
+        let other = Message::signal(VIEW_PATH, CALENDAR_INTERFACE, "ObjectsAdded")
+            .unwrap()
+            .build(&(Vec::<String>::new(),))
+            .unwrap();
+        assert!(!rule.matches(&other).unwrap());
+    }
+
+    #[test]
+    fn reads_changed_components() {
+        let component = "BEGIN:VEVENT\r\nUID:1\r\nEND:VEVENT\r\n";
+        for member in ["ObjectsAdded", "ObjectsModified"] {
+            assert_eq!(
+                ViewChange::from_message(&signal(member, &[component])).unwrap(),
+                Some(ViewChange::Changed(vec![component.to_string()]))
+            );
+        }
+    }
+
+    #[test]
+    fn reads_removed_components() {
+        let message = signal("ObjectsRemoved", &["1\n", "2\n20250106T090000Z", "3"]);
+        assert_eq!(
+            ViewChange::from_message(&message).unwrap(),
+            Some(ViewChange::Removed(vec![
+                ("1".to_string(), None),
+                ("2".to_string(), Some("20250106T090000Z".to_string())),
+                ("3".to_string(), None),
+            ]))
+        );
+    }
+
+    #[test]
+    fn ignores_other_signals() {
+        assert_eq!(
+            ViewChange::from_message(&signal("Progress", &[])).unwrap(),
+            None
+        );
+        let invalid = Message::signal(VIEW_PATH, CALENDAR_VIEW_INTERFACE, "ObjectsAdded")
+            .unwrap()
+            .build(&(42u32,))
+            .unwrap();
+        assert!(ViewChange::from_message(&invalid).is_err());
+    }
+}
@@ -1,4 +1,6 @@
 mod eds;
+mod eds_calendar;
 mod macros;
 
 pub use eds::*;
+pub use eds_calendar::*;